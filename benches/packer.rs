@@ -0,0 +1,95 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use image_packer::*;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+const TEXTURE_SIZE: [usize; 2] = [2048, 2048];
+
+type Generator = fn(&mut StdRng) -> Vec<[usize; 2]>;
+
+fn uniform_image_sizes(rng: &mut StdRng) -> Vec<[usize; 2]> {
+    (0..2000).map(|_|[rng.gen_range(8..257), rng.gen_range(8..257)]).collect()
+}
+
+// glyphs have similar heights and various widths
+fn glyph_image_sizes(rng: &mut StdRng) -> Vec<[usize; 2]> {
+    (0..5000).map(|_|[rng.gen_range(2..25), rng.gen_range(14..21)]).collect()
+}
+
+fn huge_and_tiny_image_sizes(rng: &mut StdRng) -> Vec<[usize; 2]> {
+    let mut image_sizes: Vec<[usize; 2]> = (0..10).map(|_|[rng.gen_range(512..1537), rng.gen_range(512..1537)]).collect();
+    image_sizes.extend((0..5000).map(|_|[rng.gen_range(2..9), rng.gen_range(2..9)]));
+    image_sizes
+}
+
+fn power_of_two_image_sizes(rng: &mut StdRng) -> Vec<[usize; 2]> {
+    (0..2000).map(|_|[1 << rng.gen_range(3..9), 1 << rng.gen_range(3..9)]).collect()
+}
+
+fn small_image_sizes(rng: &mut StdRng) -> Vec<[usize; 2]> {
+    (0..50000).map(|_|[rng.gen_range(4..33), rng.gen_range(4..33)]).collect()
+}
+
+fn distributions() -> Vec<(&'static str, Vec<[usize; 2]>)> {
+    let generators: [(&'static str, Generator); 5] = [
+        ("uniform", uniform_image_sizes),
+        ("glyph", glyph_image_sizes),
+        ("huge and tiny", huge_and_tiny_image_sizes),
+        ("power of two", power_of_two_image_sizes),
+        ("50k small images", small_image_sizes),
+    ];
+    generators.iter().map(|(name, generate)|(*name, generate(&mut StdRng::seed_from_u64(0)))).collect()
+}
+
+// runtime is measured by criterion, texture count and occupancy are printed once per distribution
+fn print_occupancy(name: &str, packer: &Packer, image_sizes: &[[usize; 2]]) {
+    let results = packer.pack_with_statistics(image_sizes).unwrap();
+    let used_area: usize = results.iter().map(|a|a.statistics.used_area).sum();
+    let total_area = results.len() * packer.texture_size[0] * packer.texture_size[1];
+    println!(
+        "{} (rotate: {}): images {}, textures {}, occupancy {:.2}%, last texture occupancy {:.2}%",
+        name,
+        packer.enable_rotate,
+        image_sizes.len(),
+        results.len(),
+        used_area as f64 * 100.0 / total_area as f64,
+        results.last().map_or(0.0, |a|a.statistics.occupancy));
+}
+
+fn bench_pack(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pack");
+    group.sample_size(10);
+    for (name, image_sizes) in distributions() {
+        for enable_rotate in [false, true] {
+            let packer = Packer {
+                texture_size: TEXTURE_SIZE,
+                spacing: 1,
+                enable_rotate,
+            };
+            print_occupancy(name, &packer, &image_sizes);
+            let parameter = if enable_rotate { "rotate" } else { "no rotate" };
+            group.bench_with_input(BenchmarkId::new(name, parameter), &image_sizes, |b, image_sizes|b.iter(||packer.pack(image_sizes).unwrap()));
+        }
+    }
+    group.finish();
+}
+
+// glyph cache usage. a texture is filled with glyphs, then a glyph is replaced by another one each round
+fn bench_allocator_churn(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut allocator = AtlasAllocator::new(Packer { texture_size: TEXTURE_SIZE, spacing: 1, enable_rotate: false }).unwrap();
+    let mut ids: Vec<AllocationId> = Vec::new();
+    while ids.len() < 8000 {
+        ids.push(allocator.allocate([rng.gen_range(2..25), rng.gen_range(14..21)]).unwrap().id);
+    }
+    println!("allocator: allocations {}, occupancy {:.2}%", allocator.len(), allocator.occupancy());
+
+    c.bench_function("allocator/deallocate and allocate 8000 glyphs", |b|b.iter(|| {
+        let i = rng.gen_range(0..ids.len());
+        assert!(allocator.deallocate(ids[i]));
+        ids[i] = allocator.allocate([rng.gen_range(2..25), rng.gen_range(14..21)]).unwrap().id;
+    }));
+}
+
+criterion_group!(benches, bench_pack, bench_allocator_churn);
+criterion_main!(benches);
@@ -0,0 +1,126 @@
+use crate::packer::{Packer, Rect, Spaces};
+use std::collections::btree_map::BTreeMap;
+
+// thin spaces are kept unlike in `Packer::pack`, because released neighbours can be merged with them later
+const ALLOCATOR_MIN_SPACE_SIZE: usize = 1;
+
+// Long-lived allocator for a single texture.
+// Unlike `Packer::pack` allocations can be added and removed at any time.
+#[derive(Debug)]
+pub struct AtlasAllocator {
+    packer: Packer,
+    spaces: Spaces,
+    entries: BTreeMap<AllocationId, Entry>,
+    next_id: usize,
+}
+
+// Identifies an allocation of an `AtlasAllocator`. ids are never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AllocationId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Allocation {
+    pub id: AllocationId,
+    pub position: [usize; 2],
+    pub rotated: bool,
+}
+
+#[derive(Debug)]
+struct Entry {
+    allocation: Allocation,
+    size: [usize; 2],
+}
+
+impl Entry {
+    fn rect(&self) -> Rect {
+        let size = if self.allocation.rotated { [self.size[1], self.size[0]] } else { self.size };
+        Rect { position: self.allocation.position, size }
+    }
+}
+
+impl AtlasAllocator {
+    pub fn new(packer: Packer) -> Result<AtlasAllocator, String> {
+        packer.validate()?;
+        let spaces = Spaces::new(packer.texture_size_with_spacing());
+        Ok(AtlasAllocator { packer, spaces, entries: BTreeMap::new(), next_id: 0 })
+    }
+
+    pub fn texture_size(&self) -> [usize; 2] {
+        self.packer.texture_size
+    }
+
+    pub fn allocate(&mut self, size: [usize; 2]) -> Option<Allocation> {
+        if size[0] == 0 || size[1] == 0 {
+            return None;
+        }
+        let fits = size[0] <= self.packer.texture_size[0] && size[1] <= self.packer.texture_size[1];
+        let fits_rotated = self.packer.enable_rotate && size[1] <= self.packer.texture_size[0] && size[0] <= self.packer.texture_size[1];
+        if !fits && !fits_rotated {
+            return None;
+        }
+
+        let (position, rotated) = self.packer.place(&mut self.spaces, size, ALLOCATOR_MIN_SPACE_SIZE)?;
+        let allocation = Allocation { id: AllocationId(self.next_id), position, rotated };
+        self.entries.insert(allocation.id, Entry { allocation, size });
+        self.next_id += 1;
+        Some(allocation)
+    }
+
+    // The released area is merged with its free neighbours in place,
+    // so the cost depends on the spaces around the allocation, not on the number of allocations.
+    pub fn deallocate(&mut self, id: AllocationId) -> bool {
+        let entry = match self.entries.remove(&id) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let spacing = self.packer.spacing;
+        let rect = entry.rect();
+        let rect_with_spacing = Rect { position: rect.position, size: [rect.size[0] + spacing, rect.size[1] + spacing] };
+        self.spaces.release(&rect_with_spacing, ALLOCATOR_MIN_SPACE_SIZE);
+        true
+    }
+
+    // returns the number of freed allocations
+    pub fn deallocate_many<I: IntoIterator<Item = AllocationId>>(&mut self, ids: I) -> usize {
+        ids.into_iter().filter(|a|self.deallocate(*a)).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.spaces = Spaces::new(self.packer.texture_size_with_spacing());
+    }
+
+    pub fn get(&self, id: AllocationId) -> Option<Allocation> {
+        self.entries.get(&id).map(|a|a.allocation)
+    }
+
+    // Rectangle actually covered by the allocation in the texture, rotation applied and spacing excluded.
+    pub fn allocated_rect(&self, id: AllocationId) -> Option<Rect> {
+        self.entries.get(&id).map(Entry::rect)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = AllocationId> + '_ {
+        self.entries.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn used_area(&self) -> usize {
+        self.entries.values().map(|a|a.size[0] * a.size[1]).sum()
+    }
+
+    pub fn free_area(&self) -> usize {
+        self.packer.texture_size[0] * self.packer.texture_size[1] - self.used_area()
+    }
+
+    // Percentage of used area to texture area, same as `TextureStatistics::occupancy`.
+    pub fn occupancy(&self) -> f64 {
+        self.used_area() as f64 * 100.0 / (self.packer.texture_size[0] * self.packer.texture_size[1]) as f64
+    }
+}
@@ -0,0 +1,51 @@
+use image_packer::*;
+use std::error;
+use std::fs::File;
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+#[derive(Debug)]
+struct Args {
+    input_data_path: String,
+    output_path: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Result<Args> {
+        let matches = clap::Command::new("html-report")
+            .about("Generate html report from packed information json")
+            .arg(
+                clap::Arg::new("input-data-path")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(
+                clap::Arg::new("output-path")
+                    .takes_value(true)
+            )
+            .get_matches();
+
+        Ok(Args {
+            input_data_path: matches.value_of("input-data-path").unwrap().to_string(),
+            output_path: matches.value_of("output-path").map(String::from),
+        })
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse()?;
+    let input_data_path = Path::new(&args.input_data_path);
+    let output_data: OutputData = serde_json::from_reader(File::open(input_data_path)?)?;
+
+    // textures are next to the json, and the report refers them relatively from where it is written
+    let output_path = match args.output_path {
+        Some(path) => Path::new(&path).to_path_buf(),
+        None => input_data_path.with_extension("html"),
+    };
+    let texture_dir = report::relative_texture_dir(
+        output_path.parent().unwrap_or_else(||Path::new("")),
+        input_data_path.parent().unwrap_or_else(||Path::new("")))?;
+    std::fs::write(output_path, report::html_report(&output_data, &texture_dir))?;
+    Ok(())
+}
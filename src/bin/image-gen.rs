@@ -90,7 +90,7 @@ impl Args {
             }
         }
 
-        return Ok(args);
+        Ok(args)
     }
 }
 
@@ -100,10 +100,7 @@ fn image_gen(args: Args) -> Result<()> {
     if !dir_path.is_dir() {
         std::fs::create_dir(dir_path)?;
     }
-    let mut buffer = Vec::<u8>::with_capacity((args.width_range[1] as usize) * (args.height_range[1] as usize) * 4);
-    for _ in 0..buffer.capacity() {
-        buffer.push(0);
-    }
+    let mut buffer: Vec<u8> = vec![0; (args.width_range[1] as usize) * (args.height_range[1] as usize) * 4];
 
     for i in 1..(args.number + 1) {
         let path = dir_path.join(Path::new(&format!("{}{:03}", args.prefix, i))).with_extension("png");
@@ -0,0 +1,143 @@
+use image::RgbaImage;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// Manifest file name of the atlas with the texture prefix.
+// atlases sharing an output directory are told apart by their texture prefixes.
+pub fn manifest_filename(texture_prefix: &str) -> String {
+    format!(".image-packer-cache-{}.json", texture_prefix.replace(['/', '\\'], "_"))
+}
+
+// Record of an atlas build written in the output directory.
+// a build with the same manifest reproduces the same outputs, so it can be skipped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildManifest {
+    pub version: String,
+    pub options: serde_json::Value,
+    pub inputs: Vec<ManifestInput>,
+    pub outputs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestInput {
+    pub name: String,
+    pub path: String,
+    pub hash: String,
+}
+
+impl BuildManifest {
+    // missing or unreadable manifest is treated as no previous build
+    pub fn load(path: &Path) -> Option<BuildManifest> {
+        let content = std::fs::read(path).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(self).map_err(|e|format!("serialize manifest failed. {}", e))?;
+        std::fs::write(path, content).map_err(|e|format!("write manifest failed. {}: {}", path.display(), e))
+    }
+
+    pub fn remove(path: &Path) -> Result<(), String> {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("remove manifest failed. {}: {}", path.display(), e)),
+            _ => Ok(()),
+        }
+    }
+
+    // the previous build is reusable when nothing changed and all its outputs still exist
+    pub fn is_up_to_date(&self, previous: &BuildManifest, output_dir: &Path) -> bool {
+        self.version == previous.version
+            && self.options == previous.options
+            && self.inputs == previous.inputs
+            && previous.outputs.iter().all(|a|output_dir.join(a).is_file())
+    }
+}
+
+// 64 bit FNV-1a
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn file_hash(path: &Path) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|e|format!("read failed. {}: {}", path.display(), e))?;
+    Ok(format!("{:016x}", content_hash(&bytes)))
+}
+
+// Decoded images shared between atlases. only images used more than once are kept,
+// and each is dropped when its last user has taken it.
+pub struct DecodeCache {
+    entries: Mutex<HashMap<PathBuf, DecodeCacheEntry>>,
+}
+
+struct DecodeCacheEntry {
+    remaining_uses: usize,
+    image: Option<Arc<RgbaImage>>,
+}
+
+impl DecodeCache {
+    pub fn new<'a>(paths: impl Iterator<Item = &'a Path>) -> DecodeCache {
+        let mut entries = HashMap::<PathBuf, DecodeCacheEntry>::new();
+        for path in paths {
+            entries.entry(Self::key(path)).or_insert(DecodeCacheEntry { remaining_uses: 0, image: None }).remaining_uses += 1;
+        }
+        entries.retain(|_, a|a.remaining_uses > 1);
+        DecodeCache { entries: Mutex::new(entries) }
+    }
+
+    // take a use without decoding, when the user is skipped
+    pub fn release(&self, path: &Path) {
+        let key = Self::key(path);
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get_mut(&key) {
+            entry.remaining_uses -= 1;
+            if entry.remaining_uses == 0 {
+                entries.remove(&key);
+            }
+        }
+    }
+
+    fn key(path: &Path) -> PathBuf {
+        std::fs::canonicalize(path).unwrap_or_else(|_|path.to_path_buf())
+    }
+
+    // shared images are decoded by their first user and kept for the others
+    pub fn decode(&self, path: &Path) -> Result<Arc<RgbaImage>, String> {
+        let key = Self::key(path);
+        let shared = {
+            let mut entries = self.entries.lock().unwrap();
+            match entries.get_mut(&key) {
+                Some(entry) => {
+                    entry.remaining_uses -= 1;
+                    if let Some(image) = entry.image.clone() {
+                        if entry.remaining_uses == 0 {
+                            entries.remove(&key);
+                        }
+                        return Ok(image);
+                    }
+                    true
+                },
+                None => false,
+            }
+        };
+
+        let image = Arc::new(image::open(path).map_err(|e|e.to_string())?.to_rgba8());
+        if shared {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(entry) = entries.get_mut(&key) {
+                if entry.remaining_uses > 0 {
+                    entry.image = Some(image.clone());
+                } else {
+                    entries.remove(&key);
+                }
+            }
+        }
+        Ok(image)
+    }
+}
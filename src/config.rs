@@ -0,0 +1,181 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// Settings of an atlas build. keys are the long command line option names with '-' replaced by '_'.
+// unset values fall back to values of the enclosing project, then to command line defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtlasConfig {
+    pub name: Option<String>,
+    pub input_paths: Option<Vec<String>>,
+    pub output_path: Option<String>,
+    pub texture_size: Option<[usize; 2]>,
+    pub texture_prefix: Option<String>,
+    pub spacing: Option<usize>,
+    pub enable_rotate: Option<bool>,
+    pub input_filename_pattern: Option<String>,
+    pub recursive: Option<bool>,
+    pub any_extension: Option<bool>,
+    pub on_decode_error: Option<String>,
+    pub output_data_filename: Option<String>,
+    // formats of packed information files. see format::DataFormat
+    pub data_formats: Option<Vec<String>>,
+    pub output_statistics: Option<bool>,
+    pub debug_overlay: Option<bool>,
+    pub html_report: Option<bool>,
+    pub html_report_filename: Option<String>,
+    // build an atlas for each top level subdirectory of the input directories
+    pub split_subdirectories: Option<bool>,
+    // keys which are not settings. ProjectConfig::load rejects them to catch typos
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, toml::Value>,
+}
+
+// Project file describing one or more atlases. top level atlas settings are shared by all atlases.
+// when no atlas is listed, the top level settings describe the only atlas.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub jobs: Option<usize>,
+    #[serde(default)]
+    pub textures_in_flight: Option<usize>,
+    #[serde(flatten)]
+    pub defaults: AtlasConfig,
+    #[serde(default, rename = "atlas")]
+    pub atlases: Vec<AtlasConfig>,
+}
+
+impl AtlasConfig {
+    // values set in other take precedence
+    pub fn merge(self, other: AtlasConfig) -> AtlasConfig {
+        AtlasConfig {
+            name: other.name.or(self.name),
+            input_paths: other.input_paths.or(self.input_paths),
+            output_path: other.output_path.or(self.output_path),
+            texture_size: other.texture_size.or(self.texture_size),
+            texture_prefix: other.texture_prefix.or(self.texture_prefix),
+            spacing: other.spacing.or(self.spacing),
+            enable_rotate: other.enable_rotate.or(self.enable_rotate),
+            input_filename_pattern: other.input_filename_pattern.or(self.input_filename_pattern),
+            recursive: other.recursive.or(self.recursive),
+            any_extension: other.any_extension.or(self.any_extension),
+            on_decode_error: other.on_decode_error.or(self.on_decode_error),
+            output_data_filename: other.output_data_filename.or(self.output_data_filename),
+            data_formats: other.data_formats.or(self.data_formats),
+            output_statistics: other.output_statistics.or(self.output_statistics),
+            debug_overlay: other.debug_overlay.or(self.debug_overlay),
+            html_report: other.html_report.or(self.html_report),
+            html_report_filename: other.html_report_filename.or(self.html_report_filename),
+            split_subdirectories: other.split_subdirectories.or(self.split_subdirectories),
+            unknown: self.unknown.into_iter().chain(other.unknown).collect(),
+        }
+    }
+
+    // make relative input and output paths relative to base directory
+    pub fn resolve_paths(mut self, base: &Path) -> AtlasConfig {
+        self.input_paths = self.input_paths.map(|a|a.iter().map(|source| {
+            match source.strip_prefix('@') {
+                Some(list) => format!("@{}", base.join(list).display()),
+                None => base.join(source).display().to_string(),
+            }
+        }).collect());
+        self.output_path = self.output_path.map(|a|base.join(a).display().to_string());
+        self
+    }
+
+    // Expand into atlases named by the top level subdirectories of input directories.
+    // subdirectories with the same name in several inputs make one atlas. atlases are written to the output path,
+    // with the subdirectory name as the texture prefix and the data file stem, or prepended to the ones set.
+    pub fn split_subdirectories(&self) -> Result<Vec<AtlasConfig>, String> {
+        if !self.split_subdirectories.unwrap_or(false) {
+            return Ok(vec![self.clone()]);
+        }
+
+        let mut subdirectories = BTreeMap::<String, Vec<String>>::new();
+        for source in self.input_paths.iter().flatten() {
+            let dir = Path::new(source);
+            if !dir.is_dir() {
+                return Err(format!("input must be a directory to split subdirectories. {}", source));
+            }
+            let entries = std::fs::read_dir(dir).map_err(|e|format!("read_dir failed. {}: {}", dir.display(), e))?;
+            for entry in entries {
+                let path = entry.map_err(|e|format!("read_dir failed. {}: {}", dir.display(), e))?.path();
+                if path.is_dir() {
+                    let name = crate::input::file_name(&path)?.to_string();
+                    subdirectories.entry(name).or_default().push(path.display().to_string());
+                }
+            }
+        }
+
+        Ok(subdirectories.into_iter().map(|(subdirectory, input_paths)| {
+            let html_report_filename = match self.html_report_filename {
+                Some(ref filename) => Some(prepend_file_name(&subdirectory, filename)),
+                None if self.html_report.unwrap_or(false) => Some(format!("{}.html", subdirectory)),
+                None => None,
+            };
+            AtlasConfig {
+                name: Some(match self.name {
+                    Some(ref name) => format!("{}/{}", name, subdirectory),
+                    None => subdirectory.clone(),
+                }),
+                input_paths: Some(input_paths),
+                texture_prefix: Some(match self.texture_prefix {
+                    Some(ref prefix) => format!("{}-{}", subdirectory, prefix),
+                    None => subdirectory.clone(),
+                }),
+                output_data_filename: Some(match self.output_data_filename {
+                    Some(ref filename) => prepend_file_name(&subdirectory, filename),
+                    None => format!("{}.json", subdirectory),
+                }),
+                html_report_filename,
+                split_subdirectories: None,
+                ..self.clone()
+            }
+        }).collect())
+    }
+}
+
+// "ui", "data/info.json" -> "data/ui-info.json"
+fn prepend_file_name(prefix: &str, filename: &str) -> String {
+    match filename.rfind('/') {
+        Some(i) => format!("{}/{}-{}", &filename[..i], prefix, &filename[i + 1..]),
+        None => format!("{}-{}", prefix, filename),
+    }
+}
+
+impl ProjectConfig {
+    // load a toml project file, or a json one when the extension is .json
+    pub fn load(path: &Path) -> Result<ProjectConfig, String> {
+        let content = std::fs::read_to_string(path).map_err(|e|format!("read project file failed. {}: {}", path.display(), e))?;
+        let config: ProjectConfig = if path.extension().is_some_and(|a|a == "json") {
+            serde_json::from_str(&content).map_err(|e|format!("parse project file failed. {}: {}", path.display(), e))?
+        } else {
+            toml::from_str(&content).map_err(|e|format!("parse project file failed. {}: {}", path.display(), e))?
+        };
+
+        let mut unknown: Vec<String> = config.defaults.unknown.keys().cloned().collect();
+        for (index, atlas) in config.atlases.iter().enumerate() {
+            unknown.extend(atlas.unknown.keys().map(|a|format!("atlas[{}].{}", index, a)));
+        }
+        if !unknown.is_empty() {
+            return Err(format!("unknown keys in project file. {}: {}", path.display(), unknown.join(", ")));
+        }
+
+        let base = path.parent().unwrap_or_else(||Path::new(""));
+        Ok(ProjectConfig {
+            jobs: config.jobs,
+            textures_in_flight: config.textures_in_flight,
+            defaults: config.defaults.resolve_paths(base),
+            atlases: config.atlases.into_iter().map(|a|a.resolve_paths(base)).collect(),
+        })
+    }
+
+    // atlas settings with top level settings applied
+    pub fn atlas_configs(&self) -> Vec<AtlasConfig> {
+        if self.atlases.is_empty() {
+            return vec![self.defaults.clone()];
+        }
+        self.atlases.iter().map(|a|self.defaults.clone().merge(a.clone())).collect()
+    }
+}
@@ -0,0 +1,211 @@
+pub mod binary;
+pub mod c_header;
+pub mod css;
+pub mod godot;
+pub mod libgdx;
+pub mod plist;
+pub mod rust;
+pub mod starling;
+pub mod texture_packer;
+
+use crate::{ImageLayoutInfo, OutputData, Rotation};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// Formats of packed information files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DataFormat {
+    // OutputData json
+    Information,
+    TexturePackerHash,
+    TexturePackerArray,
+    // libGDX / Spine atlas
+    Libgdx,
+    // Godot AtlasTexture resources
+    Godot,
+    // Cocos2d-x property list format 2 and 3
+    Cocos2dPlist,
+    Cocos2dPlistV3,
+    // Starling / Sparrow xml
+    Starling,
+    // css classes and scss mixins for web sprites
+    Css,
+    Scss,
+    // rust module with sprite constants
+    Rust,
+    // c header with a sprite table
+    CHeader,
+    // compact binary form of OutputData
+    Binary,
+}
+
+impl FromStr for DataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "information" => Ok(DataFormat::Information),
+            "texture-packer-hash" => Ok(DataFormat::TexturePackerHash),
+            "texture-packer-array" => Ok(DataFormat::TexturePackerArray),
+            "libgdx" => Ok(DataFormat::Libgdx),
+            "godot" => Ok(DataFormat::Godot),
+            "cocos2d-plist" => Ok(DataFormat::Cocos2dPlist),
+            "cocos2d-plist-v3" => Ok(DataFormat::Cocos2dPlistV3),
+            "starling" => Ok(DataFormat::Starling),
+            "css" => Ok(DataFormat::Css),
+            "scss" => Ok(DataFormat::Scss),
+            "rust" => Ok(DataFormat::Rust),
+            "c-header" => Ok(DataFormat::CHeader),
+            "binary" => Ok(DataFormat::Binary),
+            _ => Err(format!("unknown data format. {}", s)),
+        }
+    }
+}
+
+impl DataFormat {
+    // rotation directions the format can describe. exporters fail on sprites rotated in other directions,
+    // so the direction should be chosen before packing, and rotation disabled when there is none
+    pub fn rotations(&self) -> &'static [Rotation] {
+        match self {
+            DataFormat::Libgdx => &[Rotation::CounterClockwise],
+            DataFormat::Godot | DataFormat::Css | DataFormat::Scss => &[],
+            DataFormat::TexturePackerHash | DataFormat::TexturePackerArray | DataFormat::Cocos2dPlist | DataFormat::Cocos2dPlistV3 | DataFormat::Starling => &[Rotation::Clockwise],
+            DataFormat::Information | DataFormat::Rust | DataFormat::CHeader | DataFormat::Binary => &[Rotation::Clockwise, Rotation::CounterClockwise],
+        }
+    }
+}
+
+// a file written by an exporter. filename is relative to the output directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedFile {
+    pub filename: String,
+    pub content: Vec<u8>,
+}
+
+pub fn export(
+    format: DataFormat,
+    data: &OutputData,
+    output_data_filename: &str,
+) -> Result<Vec<ExportedFile>, String> {
+    match format {
+        DataFormat::Information => {
+            let content = serde_json::to_vec(data).map_err(|e|format!("serialize output data failed. {}", e))?;
+            Ok(vec![ExportedFile { filename: String::from(output_data_filename), content }])
+        },
+        DataFormat::TexturePackerHash => texture_packer::export(data, texture_packer::FramesLayout::Hash),
+        DataFormat::TexturePackerArray => texture_packer::export(data, texture_packer::FramesLayout::Array),
+        DataFormat::Libgdx => libgdx::export(data, output_data_filename),
+        DataFormat::Godot => godot::export(data, output_data_filename),
+        DataFormat::Cocos2dPlist => plist::export(data, 2),
+        DataFormat::Cocos2dPlistV3 => plist::export(data, 3),
+        DataFormat::Starling => starling::export(data),
+        DataFormat::Css => css::export_css(data, output_data_filename),
+        DataFormat::Scss => css::export_scss(data, output_data_filename),
+        DataFormat::Rust => rust::export(data, output_data_filename),
+        DataFormat::CHeader => c_header::export(data, output_data_filename),
+        DataFormat::Binary => binary::export(data, output_data_filename),
+    }
+}
+
+// formats which describe only a fixed rotation direction fail when sprites are rotated in the other direction
+pub(crate) fn check_rotation(data: &OutputData, expected: Rotation, format_name: &str) -> Result<(), String> {
+    match data.image_layouts.iter().find(|a|a.rotated) {
+        Some(image) if data.rotation != expected => Err(format!("{} expects sprites rotated {}, but they are rotated {}. {}", format_name, expected, data.rotation, image.name)),
+        _ => Ok(()),
+    }
+}
+
+// newlines are escaped so that they survive in attribute values
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// contents of a double quoted string literal, for formats with c like escapes.
+// control characters without a short escape are written by escape_control
+fn escape_string_literal(s: &str, escape_control: fn(char) -> String) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ if c.is_control() => escaped.push_str(&escape_control(c)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// control characters are written as octal escapes of their utf-8 bytes. octal escapes end after 3 digits,
+// unlike \x which would take following hex digits. '?' is escaped so that "??" never starts a trigraph
+pub(crate) fn escape_c_string(s: &str) -> String {
+    let escaped = escape_string_literal(s, |c|c.encode_utf8(&mut [0; 4]).bytes().map(|a|format!("\\{:03o}", a)).collect());
+    escaped.replace('?', "\\?")
+}
+
+// godot strings and resource paths
+pub(crate) fn escape_godot_string(s: &str) -> String {
+    escape_string_literal(s, |c|format!("\\u{:04x}", c as u32))
+}
+
+// Identifiers made from names by sanitize. fails when different names make the same identifier.
+pub(crate) fn unique_identifiers<'a>(
+    names: impl Iterator<Item = &'a str>,
+    sanitize: impl Fn(&str) -> String,
+) -> Result<Vec<String>, String> {
+    let mut identifiers = Vec::<String>::new();
+    let mut sources = HashMap::<String, &str>::new();
+    for name in names {
+        let identifier = sanitize(name);
+        if let Some(other) = sources.insert(identifier.clone(), name) {
+            return Err(format!("names {} and {} make the same identifier {}", other, name, identifier));
+        }
+        identifiers.push(identifier);
+    }
+    Ok(identifiers)
+}
+
+// output data written before the texture size was recorded has [0, 0]. formats which need it fail on that
+pub(crate) fn check_texture_size(data: &OutputData) -> Result<(), String> {
+    if data.texture_size[0] == 0 || data.texture_size[1] == 0 {
+        return Err(format!("texture size is unknown ({}, {}). pack again to record it in the output data", data.texture_size[0], data.texture_size[1]));
+    }
+    Ok(())
+}
+
+// normalized texture coordinates of the top left and the bottom right of the rect placed in the texture.
+// the texture size must be checked by check_texture_size
+pub(crate) fn uv(data: &OutputData, image: &ImageLayoutInfo) -> [f32; 4] {
+    let size = if image.rotated { [image.size[1], image.size[0]] } else { image.size };
+    let [w, h] = [data.texture_size[0] as f64, data.texture_size[1] as f64];
+    [
+        (image.position[0] as f64 / w) as f32,
+        (image.position[1] as f64 / h) as f32,
+        ((image.position[0] + size[0]) as f64 / w) as f32,
+        ((image.position[1] + size[1]) as f64 / h) as f32,
+    ]
+}
+
+// file name of a texture with its extension replaced
+pub fn page_filename(texture: &str, extension: &str) -> String {
+    match texture.rfind('.') {
+        Some(i) => format!("{}.{}", &texture[..i], extension),
+        None => format!("{}.{}", texture, extension),
+    }
+}
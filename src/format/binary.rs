@@ -0,0 +1,226 @@
+use crate::{ImageLayoutInfo, OutputData, Rotation, TextureStatistics};
+use super::ExportedFile;
+use std::convert::{TryFrom, TryInto};
+use std::path::Path;
+
+// Compact binary form of OutputData. all values are little endian.
+//
+// header (32 bytes)
+//   magic "IPAK", version u16, flags u16 (bit 0: has statistics, bit 1: rotated sprites are rotated counter clockwise),
+//   texture width u32, texture height u32, texture count u32, sprite count u32, string table size u32, reserved u32
+// texture records (8 bytes each)
+//   name offset u32, name length u32
+// sprite records (32 bytes each)
+//   name offset u32, name length u32, texture u32, x u32, y u32, width u32, height u32, flags u32 (bit 0: rotated)
+// statistics records (40 bytes each, one per texture when the header flag is set)
+//   sprite count u32, reserved u32, used area u64, wasted area u64, occupancy f64, largest free rect width u32, height u32
+// string table
+//   utf-8 names. offsets are relative to the start of the table
+pub const MAGIC: [u8; 4] = *b"IPAK";
+pub const VERSION: u16 = 1;
+pub const HEADER_SIZE: usize = 32;
+pub const TEXTURE_RECORD_SIZE: usize = 8;
+pub const SPRITE_RECORD_SIZE: usize = 32;
+pub const STATISTICS_RECORD_SIZE: usize = 40;
+
+const FLAG_STATISTICS: u16 = 1;
+const FLAG_COUNTER_CLOCKWISE: u16 = 2;
+const FLAG_ROTATED: u32 = 1;
+
+fn to_u32(value: usize, what: &str) -> Result<u32, String> {
+    u32::try_from(value).map_err(|_|format!("{} is too large for binary format. {}", what, value))
+}
+
+struct StringTable {
+    bytes: Vec<u8>,
+}
+
+impl StringTable {
+    fn push(&mut self, s: &str) -> Result<[u32; 2], String> {
+        let offset = to_u32(self.bytes.len(), "string table size")?;
+        self.bytes.extend_from_slice(s.as_bytes());
+        Ok([offset, to_u32(s.len(), "name length")?])
+    }
+}
+
+pub fn write(data: &OutputData) -> Result<Vec<u8>, String> {
+    if let Some(ref statistics) = data.statistics {
+        if statistics.len() != data.textures.len() {
+            return Err(format!("statistics count {} differs from texture count {}", statistics.len(), data.textures.len()));
+        }
+    }
+
+    let mut strings = StringTable { bytes: Vec::new() };
+    let mut records = Vec::<u8>::with_capacity(data.textures.len() * TEXTURE_RECORD_SIZE + data.image_layouts.len() * SPRITE_RECORD_SIZE);
+    for texture in data.textures.iter() {
+        for value in strings.push(texture)? {
+            records.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    for image in data.image_layouts.iter() {
+        let [name_offset, name_length] = strings.push(&image.name)?;
+        let values = [
+            name_offset,
+            name_length,
+            to_u32(image.texture, "texture index")?,
+            to_u32(image.position[0], "sprite x")?,
+            to_u32(image.position[1], "sprite y")?,
+            to_u32(image.size[0], "sprite width")?,
+            to_u32(image.size[1], "sprite height")?,
+            if image.rotated { FLAG_ROTATED } else { 0 },
+        ];
+        for value in values {
+            records.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    for statistics in data.statistics.iter().flatten() {
+        records.extend_from_slice(&to_u32(statistics.sprite_count, "sprite count")?.to_le_bytes());
+        records.extend_from_slice(&0u32.to_le_bytes());
+        records.extend_from_slice(&(statistics.used_area as u64).to_le_bytes());
+        records.extend_from_slice(&(statistics.wasted_area as u64).to_le_bytes());
+        records.extend_from_slice(&statistics.occupancy.to_le_bytes());
+        records.extend_from_slice(&to_u32(statistics.largest_free_rect[0], "free rect width")?.to_le_bytes());
+        records.extend_from_slice(&to_u32(statistics.largest_free_rect[1], "free rect height")?.to_le_bytes());
+    }
+
+    let mut bytes = Vec::<u8>::with_capacity(HEADER_SIZE + records.len() + strings.bytes.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    let mut flags = 0;
+    if data.statistics.is_some() {
+        flags |= FLAG_STATISTICS;
+    }
+    if data.rotation == Rotation::CounterClockwise {
+        flags |= FLAG_COUNTER_CLOCKWISE;
+    }
+    bytes.extend_from_slice(&flags.to_le_bytes());
+    let values = [
+        to_u32(data.texture_size[0], "texture width")?,
+        to_u32(data.texture_size[1], "texture height")?,
+        to_u32(data.textures.len(), "texture count")?,
+        to_u32(data.image_layouts.len(), "sprite count")?,
+        to_u32(strings.bytes.len(), "string table size")?,
+        0,
+    ];
+    for value in values {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.append(&mut records);
+    bytes.append(&mut strings.bytes);
+    Ok(bytes)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(size).filter(|a|*a <= self.bytes.len())
+            .ok_or_else(||format!("unexpected end of binary data at {}", self.position))?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+fn string(table: &[u8], offset: u32, length: u32) -> Result<String, String> {
+    let start = offset as usize;
+    let bytes = start.checked_add(length as usize).and_then(|end|table.get(start..end))
+        .ok_or_else(||format!("string out of string table. offset {}, length {}", offset, length))?;
+    String::from_utf8(bytes.to_vec()).map_err(|e|format!("bad utf-8 string in binary data. {}", e))
+}
+
+pub fn read(bytes: &[u8]) -> Result<OutputData, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != MAGIC {
+        return Err(String::from("not an image-packer binary data"));
+    }
+    let version = reader.u16()?;
+    if version != VERSION {
+        return Err(format!("unsupported binary data version. {}", version));
+    }
+    let flags = reader.u16()?;
+    let texture_size = [reader.u32()? as usize, reader.u32()? as usize];
+    let texture_count = reader.u32()? as usize;
+    let sprite_count = reader.u32()? as usize;
+    let string_table_size = reader.u32()? as usize;
+    reader.u32()?;
+
+    let statistics_count = if flags & FLAG_STATISTICS != 0 { texture_count } else { 0 };
+    // counts are not trusted, and may overflow usize on 32 bit targets
+    let records_size = [(texture_count, TEXTURE_RECORD_SIZE), (sprite_count, SPRITE_RECORD_SIZE), (statistics_count, STATISTICS_RECORD_SIZE)]
+        .iter()
+        .try_fold(0usize, |size, &(count, record_size)|count.checked_mul(record_size).and_then(|a|size.checked_add(a)))
+        .ok_or("binary data header counts overflow")?;
+    let expected_size = records_size
+        .checked_add(HEADER_SIZE)
+        .and_then(|a|a.checked_add(string_table_size))
+        .ok_or("binary data header counts overflow")?;
+    if bytes.len() != expected_size {
+        return Err(format!("binary data size {} differs from the size in its header {}", bytes.len(), expected_size));
+    }
+    let table = &bytes[HEADER_SIZE + records_size..];
+
+    let mut textures = Vec::with_capacity(texture_count);
+    for _ in 0..texture_count {
+        let (offset, length) = (reader.u32()?, reader.u32()?);
+        textures.push(string(table, offset, length)?);
+    }
+
+    let mut image_layouts = Vec::with_capacity(sprite_count);
+    for _ in 0..sprite_count {
+        let (offset, length) = (reader.u32()?, reader.u32()?);
+        let texture = reader.u32()? as usize;
+        if texture >= texture_count {
+            return Err(format!("texture index out of range. {}", texture));
+        }
+        let position = [reader.u32()? as usize, reader.u32()? as usize];
+        let size = [reader.u32()? as usize, reader.u32()? as usize];
+        let rotated = reader.u32()? & FLAG_ROTATED != 0;
+        image_layouts.push(ImageLayoutInfo { name: string(table, offset, length)?, texture, position, size, rotated });
+    }
+
+    let statistics = if flags & FLAG_STATISTICS != 0 {
+        let mut statistics = Vec::with_capacity(texture_count);
+        for _ in 0..texture_count {
+            let sprite_count = reader.u32()? as usize;
+            reader.u32()?;
+            statistics.push(TextureStatistics {
+                sprite_count,
+                used_area: reader.u64()? as usize,
+                wasted_area: reader.u64()? as usize,
+                occupancy: reader.f64()?,
+                largest_free_rect: [reader.u32()? as usize, reader.u32()? as usize],
+            });
+        }
+        Some(statistics)
+    } else {
+        None
+    };
+
+    let rotation = if flags & FLAG_COUNTER_CLOCKWISE != 0 { Rotation::CounterClockwise } else { Rotation::Clockwise };
+    Ok(OutputData { image_layouts, textures, texture_size, rotation, statistics })
+}
+
+pub fn export(data: &OutputData, output_data_filename: &str) -> Result<Vec<ExportedFile>, String> {
+    let filename = Path::new(output_data_filename).with_extension("bin").display().to_string();
+    Ok(vec![ExportedFile { filename, content: write(data)? }])
+}
@@ -0,0 +1,96 @@
+use crate::OutputData;
+use super::{check_texture_size, escape_c_string, unique_identifiers, uv, ExportedFile};
+use std::fmt::Write;
+use std::path::Path;
+
+// "hero/walk_01.png" -> "SPRITE_ID_HERO_WALK_01"
+// the SPRITE_ID_ prefix keeps enum constants apart from the other SPRITE_ names in the header
+pub fn identifier(name: &str) -> String {
+    let stem = Path::new(name).with_extension("").display().to_string();
+    let sanitized: String = stem.chars().map(|c|if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    format!("SPRITE_ID_{}", sanitized)
+}
+
+// C header with an enum of sprite ids and a static table of sprite rects indexed by them.
+// x, y, width and height are the position and the original size of a sprite, and uv covers the rect placed in the texture.
+pub fn header_source(data: &OutputData, guard: &str) -> Result<String, String> {
+    check_texture_size(data)?;
+    let identifiers = unique_identifiers(data.image_layouts.iter().map(|a|a.name.as_str()), identifier)?;
+    if let Some(name) = identifiers.iter().position(|a|a == guard).map(|a|&data.image_layouts[a].name) {
+        return Err(format!("sprite {} makes the include guard {}", name, guard));
+    }
+
+    let mut header = String::from("/* generated by image-packer. do not edit. */\n");
+    writeln!(header, "#ifndef {}\n#define {}\n", guard, guard).unwrap();
+    writeln!(header, "#define SPRITE_TEXTURE_WIDTH {}", data.texture_size[0]).unwrap();
+    writeln!(header, "#define SPRITE_TEXTURE_HEIGHT {}", data.texture_size[1]).unwrap();
+    writeln!(header, "#define SPRITE_TEXTURE_COUNT {}", data.textures.len()).unwrap();
+    writeln!(header, "#define SPRITE_COUNT {}\n", data.image_layouts.len()).unwrap();
+    header.push_str("\
+typedef struct SpriteRect {
+    int texture;
+    int x;
+    int y;
+    int width;
+    int height;
+");
+    writeln!(header, "    /* rotated 90 degrees {} in the texture */", data.rotation).unwrap();
+    header.push_str("    int rotated;\n    float u0;\n    float v0;\n    float u1;\n    float v1;\n    const char *name;\n} SpriteRect;\n\n");
+    header.push_str("enum SpriteId {\n");
+    for (index, identifier) in identifiers.iter().enumerate() {
+        writeln!(header, "    {} = {},", identifier, index).unwrap();
+    }
+    if identifiers.is_empty() {
+        // c does not allow an empty enum
+        header.push_str("    SPRITE_ID_NONE_ = 0\n");
+    }
+    header.push_str("};\n\n");
+
+    if data.textures.is_empty() {
+        header.push_str("static const char *const SPRITE_TEXTURES[1] = { 0 };\n\n");
+    } else {
+        header.push_str("static const char *const SPRITE_TEXTURES[SPRITE_TEXTURE_COUNT] = {\n");
+        for texture in data.textures.iter() {
+            writeln!(header, "    \"{}\",", escape_c_string(texture)).unwrap();
+        }
+        header.push_str("};\n\n");
+    }
+
+    if data.image_layouts.is_empty() {
+        header.push_str("static const SpriteRect SPRITES[1] = { { 0 } };\n");
+    } else {
+        header.push_str("static const SpriteRect SPRITES[SPRITE_COUNT] = {\n");
+        for (image, identifier) in data.image_layouts.iter().zip(identifiers.iter()) {
+            if image.texture >= data.textures.len() {
+                return Err(format!("texture index out of range. {}", image.name));
+            }
+            let [u0, v0, u1, v1] = uv(data, image);
+            writeln!(
+                header,
+                "    {{ {}, {}, {}, {}, {}, {}, {:?}f, {:?}f, {:?}f, {:?}f, \"{}\" }}, /* {} */",
+                image.texture,
+                image.position[0],
+                image.position[1],
+                image.size[0],
+                image.size[1],
+                image.rotated as u8,
+                u0,
+                v0,
+                u1,
+                v1,
+                escape_c_string(&image.name),
+                identifier).unwrap();
+        }
+        header.push_str("};\n");
+    }
+    writeln!(header, "\n#endif /* {} */", guard).unwrap();
+    Ok(header)
+}
+
+pub fn export(data: &OutputData, output_data_filename: &str) -> Result<Vec<ExportedFile>, String> {
+    let path = Path::new(output_data_filename).with_extension("h");
+    let filename = path.display().to_string();
+    let guard: String = filename.chars().map(|c|if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    let guard = if guard.starts_with(|c: char|c.is_ascii_digit()) { format!("H_{}", guard) } else { guard };
+    Ok(vec![ExportedFile { filename, content: header_source(data, &guard)?.into_bytes() }])
+}
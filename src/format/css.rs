@@ -0,0 +1,159 @@
+use crate::{ImageLayoutInfo, OutputData};
+use super::{check_texture_size, unique_identifiers, ExportedFile};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+const RETINA_MEDIA_QUERY: &str = "(-webkit-min-device-pixel-ratio: 2), (min-resolution: 192dpi)";
+
+struct Sprite<'a> {
+    class_name: String,
+    image: &'a ImageLayoutInfo,
+    // "name@2x.png" variant of "name.png"
+    retina: Option<&'a ImageLayoutInfo>,
+}
+
+// Sprites with their class names, with retina variants attached to their base sprites.
+// a retina variant without its base sprite is a sprite by itself.
+fn sprites(data: &OutputData) -> Result<Vec<Sprite<'_>>, String> {
+    if let Some(image) = data.image_layouts.iter().find(|a|a.rotated) {
+        return Err(format!("css sprite does not support rotated sprites. disable rotation. {}", image.name));
+    }
+
+    let names: HashMap<&str, &ImageLayoutInfo> = data.image_layouts.iter().map(|a|(a.name.as_str(), a)).collect();
+    let mut images = Vec::<(&ImageLayoutInfo, Option<&ImageLayoutInfo>)>::new();
+    for image in data.image_layouts.iter() {
+        if base_name(&image.name).is_some_and(|a|names.contains_key(a.as_str())) {
+            continue;
+        }
+        let retina = names.get(retina_name(&image.name).as_str()).copied();
+        images.push((image, retina));
+    }
+    // retina backgrounds are scaled to half the texture size
+    if images.iter().any(|a|a.1.is_some()) {
+        check_texture_size(data)?;
+    }
+
+    let class_names = unique_identifiers(images.iter().map(|a|a.0.name.as_str()), class_name)?;
+    Ok(images.into_iter().zip(class_names).map(|((image, retina), class_name)|Sprite { class_name, image, retina }).collect())
+}
+
+// "icons/home.png" -> "icons-home". names starting with a digit, "-" and a digit, or "--" are prefixed with '_'
+pub fn class_name(name: &str) -> String {
+    let stem = Path::new(name).with_extension("").display().to_string();
+    let mut class_name: String = stem.chars().map(|c|if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '-' }).collect();
+    let unprefixed = class_name.strip_prefix('-').unwrap_or(&class_name);
+    if unprefixed.is_empty() || unprefixed.starts_with(|c: char|c.is_ascii_digit() || c == '-') {
+        class_name.insert(0, '_');
+    }
+    class_name
+}
+
+fn retina_name(name: &str) -> String {
+    let path = Path::new(name);
+    match path.extension() {
+        Some(extension) => format!("{}@2x.{}", path.with_extension("").display(), extension.to_string_lossy()),
+        None => format!("{}@2x", name),
+    }
+}
+
+fn base_name(name: &str) -> Option<String> {
+    let path = Path::new(name);
+    let stem = path.with_extension("").display().to_string();
+    let base = stem.strip_suffix("@2x")?;
+    Some(match path.extension() {
+        Some(extension) => format!("{}.{}", base, extension.to_string_lossy()),
+        None => String::from(base),
+    })
+}
+
+// css length of a value scaled by 1 / scale
+fn length(value: usize, scale: usize) -> String {
+    if value == 0 { String::from("0") } else { format!("{}px", value as f64 / scale as f64) }
+}
+
+fn offset(value: usize, scale: usize) -> String {
+    if value == 0 { String::from("0") } else { format!("-{}", length(value, scale)) }
+}
+
+fn texture<'a>(data: &'a OutputData, image: &ImageLayoutInfo) -> Result<&'a str, String> {
+    data.textures.get(image.texture).map(String::as_str).ok_or_else(||format!("texture index out of range. {}", image.name))
+}
+
+// a class per sprite. retina variants override backgrounds of their base sprites on high density displays
+pub fn export_css(data: &OutputData, output_data_filename: &str) -> Result<Vec<ExportedFile>, String> {
+    let mut css = String::new();
+    let mut retina_css = String::new();
+    for sprite in sprites(data)? {
+        let image = sprite.image;
+        writeln!(css, ".{} {{", sprite.class_name).unwrap();
+        writeln!(css, "  background-image: url(\"{}\");", texture(data, image)?).unwrap();
+        css.push_str("  background-repeat: no-repeat;\n");
+        writeln!(css, "  background-position: {} {};", offset(image.position[0], 1), offset(image.position[1], 1)).unwrap();
+        writeln!(css, "  width: {};", length(image.size[0], 1)).unwrap();
+        writeln!(css, "  height: {};", length(image.size[1], 1)).unwrap();
+        css.push_str("}\n");
+
+        if let Some(retina) = sprite.retina {
+            writeln!(retina_css, "  .{} {{", sprite.class_name).unwrap();
+            writeln!(retina_css, "    background-image: url(\"{}\");", texture(data, retina)?).unwrap();
+            writeln!(retina_css, "    background-position: {} {};", offset(retina.position[0], 2), offset(retina.position[1], 2)).unwrap();
+            writeln!(retina_css, "    background-size: {} {};", length(data.texture_size[0], 2), length(data.texture_size[1], 2)).unwrap();
+            retina_css.push_str("  }\n");
+        }
+    }
+    if !retina_css.is_empty() {
+        writeln!(css, "@media {} {{\n{}}}", RETINA_MEDIA_QUERY, retina_css).unwrap();
+    }
+
+    let filename = Path::new(output_data_filename).with_extension("css").display().to_string();
+    Ok(vec![ExportedFile { filename, content: css.into_bytes() }])
+}
+
+// a map variable per sprite and mixins applying them
+pub fn export_scss(data: &OutputData, output_data_filename: &str) -> Result<Vec<ExportedFile>, String> {
+    let mut scss = String::new();
+    for sprite in sprites(data)? {
+        let image = sprite.image;
+        writeln!(
+            scss,
+            "${}: (image: \"{}\", x: {}, y: {}, width: {}, height: {});",
+            sprite.class_name,
+            texture(data, image)?,
+            length(image.position[0], 1),
+            length(image.position[1], 1),
+            length(image.size[0], 1),
+            length(image.size[1], 1)).unwrap();
+        if let Some(retina) = sprite.retina {
+            writeln!(
+                scss,
+                "${}-2x: (image: \"{}\", x: {}, y: {}, background-width: {}, background-height: {});",
+                sprite.class_name,
+                texture(data, retina)?,
+                length(retina.position[0], 2),
+                length(retina.position[1], 2),
+                length(data.texture_size[0], 2),
+                length(data.texture_size[1], 2)).unwrap();
+        }
+    }
+    scss.push_str("
+@mixin sprite($sprite) {
+  background-image: url(map-get($sprite, image));
+  background-repeat: no-repeat;
+  background-position: (-(map-get($sprite, x))) (-(map-get($sprite, y)));
+  width: map-get($sprite, width);
+  height: map-get($sprite, height);
+}
+");
+    writeln!(scss, "
+@mixin sprite-2x($sprite) {{
+  @media {} {{
+    background-image: url(map-get($sprite, image));
+    background-position: (-(map-get($sprite, x))) (-(map-get($sprite, y)));
+    background-size: map-get($sprite, background-width) map-get($sprite, background-height);
+  }}
+}}", RETINA_MEDIA_QUERY).unwrap();
+
+    let filename = Path::new(output_data_filename).with_extension("scss").display().to_string();
+    Ok(vec![ExportedFile { filename, content: scss.into_bytes() }])
+}
@@ -0,0 +1,38 @@
+use crate::OutputData;
+use super::{escape_godot_string, ExportedFile};
+use std::fmt::Write;
+use std::path::Path;
+
+// Godot 4 AtlasTexture resources, one per sprite at the sprite name with .tres extension,
+// and a script named after the output data file which preloads all of them by sprite name.
+pub fn export(data: &OutputData, output_data_filename: &str) -> Result<Vec<ExportedFile>, String> {
+    // AtlasTexture has no rotation
+    if let Some(image) = data.image_layouts.iter().find(|a|a.rotated) {
+        return Err(format!("godot atlas texture does not support rotated sprites. disable rotation. {}", image.name));
+    }
+
+    let mut files = Vec::with_capacity(data.image_layouts.len() + 1);
+    let mut index = String::from("extends RefCounted\n\nconst SPRITES = {\n");
+    for image in data.image_layouts.iter() {
+        let texture = data.textures.get(image.texture).ok_or_else(||format!("texture index out of range. {}", image.name))?;
+        let name = Path::new(&image.name).with_extension("").display().to_string();
+        let filename = format!("{}.tres", name);
+        // resource paths are relative to the resource file
+        let texture_path = format!("{}{}", "../".repeat(name.matches('/').count()), texture);
+
+        let mut resource = String::from("[gd_resource type=\"AtlasTexture\" load_steps=2 format=3]\n\n");
+        writeln!(resource, "[ext_resource type=\"Texture2D\" path=\"{}\" id=\"1\"]\n", escape_godot_string(&texture_path)).unwrap();
+        resource.push_str("[resource]\natlas = ExtResource(\"1\")\n");
+        writeln!(resource, "region = Rect2({}, {}, {}, {})", image.position[0], image.position[1], image.size[0], image.size[1]).unwrap();
+        // sprites are not trimmed
+        resource.push_str("margin = Rect2(0, 0, 0, 0)\n");
+
+        writeln!(index, "\t\"{}\": preload(\"{}\"),", escape_godot_string(&name), escape_godot_string(&filename)).unwrap();
+        files.push(ExportedFile { filename, content: resource.into_bytes() });
+    }
+    index.push_str("}\n");
+
+    let filename = Path::new(output_data_filename).with_extension("gd").display().to_string();
+    files.push(ExportedFile { filename, content: index.into_bytes() });
+    Ok(files)
+}
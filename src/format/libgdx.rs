@@ -0,0 +1,50 @@
+use crate::{OutputData, Rotation};
+use super::{check_rotation, check_texture_size, ExportedFile};
+use std::fmt::Write;
+use std::path::Path;
+
+// Region name without the extension and the index parsed from a "_<digits>" suffix, as libGDX TexturePacker names regions.
+// index is -1 when the name has no suffix.
+pub fn region_name_and_index(name: &str) -> (&str, i32) {
+    let name = match name.rfind('.') {
+        Some(i) if !name[i..].contains('/') => &name[..i],
+        _ => name,
+    };
+    if let Some(i) = name.rfind('_') {
+        let digits = &name[i + 1..];
+        if !digits.is_empty() && digits.bytes().all(|a|a.is_ascii_digit()) {
+            if let Ok(index) = digits.parse::<i32>() {
+                return (&name[..i], index);
+            }
+        }
+    }
+    (name, -1)
+}
+
+// libGDX / Spine texture atlas text format, written to one file for all pages.
+// rotated regions must be placed rotated counter clockwise. size is the original size of a region.
+pub fn export(data: &OutputData, output_data_filename: &str) -> Result<Vec<ExportedFile>, String> {
+    check_texture_size(data)?;
+    check_rotation(data, Rotation::CounterClockwise, "libgdx atlas")?;
+
+    let mut atlas = String::new();
+    for (texture_index, texture) in data.textures.iter().enumerate() {
+        atlas.push('\n');
+        writeln!(atlas, "{}", texture).unwrap();
+        writeln!(atlas, "size: {},{}", data.texture_size[0], data.texture_size[1]).unwrap();
+        atlas.push_str("format: RGBA8888\nfilter: Nearest,Nearest\nrepeat: none\n");
+        for image in data.image_layouts.iter().filter(|a|a.texture == texture_index) {
+            let (name, index) = region_name_and_index(&image.name);
+            writeln!(atlas, "{}", name).unwrap();
+            writeln!(atlas, "  rotate: {}", image.rotated).unwrap();
+            writeln!(atlas, "  xy: {}, {}", image.position[0], image.position[1]).unwrap();
+            writeln!(atlas, "  size: {}, {}", image.size[0], image.size[1]).unwrap();
+            writeln!(atlas, "  orig: {}, {}", image.size[0], image.size[1]).unwrap();
+            atlas.push_str("  offset: 0, 0\n");
+            writeln!(atlas, "  index: {}", index).unwrap();
+        }
+    }
+
+    let filename = Path::new(output_data_filename).with_extension("atlas").display().to_string();
+    Ok(vec![ExportedFile { filename, content: atlas.into_bytes() }])
+}
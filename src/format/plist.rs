@@ -0,0 +1,64 @@
+use crate::{OutputData, Rotation};
+use super::{check_rotation, check_texture_size, escape_xml, page_filename, ExportedFile};
+use std::fmt::Write;
+
+// Cocos2d-x sprite frame property list, one file per texture.
+// rotated sprites are rotated 90 degrees clockwise as cocos2d-x expects.
+pub fn export(data: &OutputData, format: u32) -> Result<Vec<ExportedFile>, String> {
+    if format != 2 && format != 3 {
+        return Err(format!("unsupported plist format. {}", format));
+    }
+    check_texture_size(data)?;
+    check_rotation(data, Rotation::Clockwise, "cocos2d plist")?;
+
+    let mut files = Vec::with_capacity(data.textures.len());
+    for (texture_index, texture) in data.textures.iter().enumerate() {
+        let mut plist = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        plist.push_str("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n");
+        plist.push_str("<plist version=\"1.0\">\n    <dict>\n        <key>frames</key>\n        <dict>\n");
+        for image in data.image_layouts.iter().filter(|a|a.texture == texture_index) {
+            let [x, y] = image.position;
+            let [w, h] = image.size;
+            writeln!(plist, "            <key>{}</key>\n            <dict>", escape_xml(&image.name)).unwrap();
+            // sprites are not trimmed, so offsets are zero and source rects cover whole sprites
+            if format == 2 {
+                write_entry(&mut plist, "frame", &format!("<string>{{{{{},{}}},{{{},{}}}}}</string>", x, y, w, h));
+                write_entry(&mut plist, "offset", "<string>{0,0}</string>");
+                write_entry(&mut plist, "rotated", if image.rotated { "<true/>" } else { "<false/>" });
+                write_entry(&mut plist, "sourceColorRect", &format!("<string>{{{{0,0}},{{{},{}}}}}</string>", w, h));
+                write_entry(&mut plist, "sourceSize", &format!("<string>{{{},{}}}</string>", w, h));
+            } else {
+                write_entry(&mut plist, "aliases", "<array/>");
+                write_entry(&mut plist, "spriteOffset", "<string>{0,0}</string>");
+                write_entry(&mut plist, "spriteSize", &format!("<string>{{{},{}}}</string>", w, h));
+                write_entry(&mut plist, "spriteSourceSize", &format!("<string>{{{},{}}}</string>", w, h));
+                write_entry(&mut plist, "textureRect", &format!("<string>{{{{{},{}}},{{{},{}}}}}</string>", x, y, w, h));
+                write_entry(&mut plist, "textureRotated", if image.rotated { "<true/>" } else { "<false/>" });
+            }
+            plist.push_str("            </dict>\n");
+        }
+        plist.push_str("        </dict>\n        <key>metadata</key>\n        <dict>\n");
+        let texture = escape_xml(texture);
+        let size = format!("<string>{{{},{}}}</string>", data.texture_size[0], data.texture_size[1]);
+        write_metadata(&mut plist, "format", &format!("<integer>{}</integer>", format));
+        if format == 3 {
+            write_metadata(&mut plist, "pixelFormat", "<string>RGBA8888</string>");
+            write_metadata(&mut plist, "premultiplyAlpha", "<false/>");
+        }
+        write_metadata(&mut plist, "realTextureFileName", &format!("<string>{}</string>", texture));
+        write_metadata(&mut plist, "size", &size);
+        write_metadata(&mut plist, "textureFileName", &format!("<string>{}</string>", texture));
+        plist.push_str("        </dict>\n    </dict>\n</plist>\n");
+
+        files.push(ExportedFile { filename: page_filename(&data.textures[texture_index], "plist"), content: plist.into_bytes() });
+    }
+    Ok(files)
+}
+
+fn write_entry(plist: &mut String, key: &str, value: &str) {
+    writeln!(plist, "                <key>{}</key>\n                {}", key, value).unwrap();
+}
+
+fn write_metadata(plist: &mut String, key: &str, value: &str) {
+    writeln!(plist, "            <key>{}</key>\n            {}", key, value).unwrap();
+}
@@ -0,0 +1,113 @@
+use crate::OutputData;
+use super::{check_texture_size, unique_identifiers, uv, ExportedFile};
+use std::fmt::Write;
+use std::path::Path;
+
+// "hero/walk_01.png" -> "HERO_WALK_01"
+pub fn const_name(name: &str) -> String {
+    let stem = Path::new(name).with_extension("").display().to_string();
+    let mut const_name: String = stem.chars().map(|c|if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    if const_name.is_empty() || const_name.starts_with(|c: char|c.is_ascii_digit()) || const_name == "_" {
+        const_name.insert(0, '_');
+    }
+    const_name
+}
+
+// "hero/walk_01.png" -> "HeroWalk01"
+pub fn variant_name(name: &str) -> String {
+    let stem = Path::new(name).with_extension("").display().to_string();
+    let mut variant_name = String::new();
+    for word in stem.split(|c: char|!c.is_ascii_alphanumeric()).filter(|a|!a.is_empty()) {
+        let mut chars = word.chars();
+        variant_name.extend(chars.next().map(|c|c.to_ascii_uppercase()));
+        variant_name.extend(chars);
+    }
+    if variant_name.is_empty() || variant_name.starts_with(|c: char|c.is_ascii_digit()) {
+        variant_name.insert_str(0, "Sprite");
+    }
+    if variant_name == "Self" {
+        variant_name.push('_');
+    }
+    variant_name
+}
+
+// Rust source with a const per sprite and an enum of sprite ids.
+// sprite consts are in the sprites module, so that they do not clash with TEXTURES or TEXTURE_SIZE.
+// this is usable from build.rs, e.g. write it into OUT_DIR and include! it.
+pub fn module_source(data: &OutputData) -> Result<String, String> {
+    check_texture_size(data)?;
+    let const_names = unique_identifiers(data.image_layouts.iter().map(|a|a.name.as_str()), const_name)?;
+    let variant_names = unique_identifiers(data.image_layouts.iter().map(|a|a.name.as_str()), variant_name)?;
+
+    let mut source = String::from("// generated by image-packer. do not edit.\n\n");
+    source.push_str("// x, y, width and height are the position and the original size of a sprite.\n");
+    writeln!(source, "// a rotated sprite is placed in the texture rotated 90 degrees {}, and uv covers the placed rect.", data.rotation).unwrap();
+    // a consumer may use only part of the module, so unused items must not warn
+    source.push_str("\
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub texture: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub rotated: bool,
+    pub uv: [f32; 4],
+}
+
+");
+    writeln!(source, "#[allow(dead_code)]\npub const TEXTURE_SIZE: [u32; 2] = [{}, {}];\n", data.texture_size[0], data.texture_size[1]).unwrap();
+    writeln!(source, "#[allow(dead_code)]\npub const TEXTURES: [&str; {}] = {:?};\n", data.textures.len(), data.textures).unwrap();
+
+    source.push_str("#[allow(dead_code, unused_imports)]\npub mod sprites {\n    use super::Sprite;\n\n");
+    for (image, const_name) in data.image_layouts.iter().zip(const_names.iter()) {
+        if image.texture >= data.textures.len() {
+            return Err(format!("texture index out of range. {}", image.name));
+        }
+        writeln!(
+            source,
+            "    pub const {}: Sprite = Sprite {{ texture: {}, x: {}, y: {}, width: {}, height: {}, rotated: {}, uv: {:?} }};",
+            const_name,
+            image.texture,
+            image.position[0],
+            image.position[1],
+            image.size[0],
+            image.size[1],
+            image.rotated,
+            uv(data, image)).unwrap();
+    }
+    source.push_str("}\n");
+
+    source.push_str("\n#[allow(dead_code)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum SpriteId {\n");
+    for variant_name in variant_names.iter() {
+        writeln!(source, "    {},", variant_name).unwrap();
+    }
+    source.push_str("}\n\n#[allow(dead_code)]\nimpl SpriteId {\n");
+    writeln!(source, "    pub const ALL: [SpriteId; {}] = [", variant_names.len()).unwrap();
+    for variant_name in variant_names.iter() {
+        writeln!(source, "        SpriteId::{},", variant_name).unwrap();
+    }
+    source.push_str("    ];\n\n    pub const fn sprite(self) -> Sprite {\n        match self {\n");
+    for (variant_name, const_name) in variant_names.iter().zip(const_names.iter()) {
+        writeln!(source, "            SpriteId::{} => sprites::{},", variant_name, const_name).unwrap();
+    }
+    source.push_str("        }\n    }\n\n    pub const fn name(self) -> &'static str {\n        match self {\n");
+    for (variant_name, image) in variant_names.iter().zip(data.image_layouts.iter()) {
+        writeln!(source, "            SpriteId::{} => {:?},", variant_name, image.name).unwrap();
+    }
+    source.push_str("        }\n    }\n}\n");
+    Ok(source)
+}
+
+// generate the module from a packed information json. for build.rs
+pub fn write_module(output_data_path: &Path, module_path: &Path) -> Result<(), String> {
+    let content = std::fs::read(output_data_path).map_err(|e|format!("read failed. {}: {}", output_data_path.display(), e))?;
+    let data: OutputData = serde_json::from_slice(&content).map_err(|e|format!("parse output data failed. {}: {}", output_data_path.display(), e))?;
+    std::fs::write(module_path, module_source(&data)?).map_err(|e|format!("write failed. {}: {}", module_path.display(), e))
+}
+
+pub fn export(data: &OutputData, output_data_filename: &str) -> Result<Vec<ExportedFile>, String> {
+    let filename = Path::new(output_data_filename).with_extension("rs").display().to_string();
+    Ok(vec![ExportedFile { filename, content: module_source(data)?.into_bytes() }])
+}
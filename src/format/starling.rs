@@ -0,0 +1,32 @@
+use crate::{OutputData, Rotation};
+use super::{check_rotation, escape_xml, page_filename, ExportedFile};
+use std::fmt::Write;
+
+// Starling / Sparrow TextureAtlas xml, one file per texture.
+// region sizes are as placed in the texture, so they are swapped for rotated sprites, while frame sizes are the original ones.
+// rotated sprites are rotated 90 degrees clockwise in the texture.
+pub fn export(data: &OutputData) -> Result<Vec<ExportedFile>, String> {
+    check_rotation(data, Rotation::Clockwise, "starling xml")?;
+    let mut files = Vec::with_capacity(data.textures.len());
+    for (texture_index, texture) in data.textures.iter().enumerate() {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(xml, "<TextureAtlas imagePath=\"{}\">", escape_xml(texture)).unwrap();
+        for image in data.image_layouts.iter().filter(|a|a.texture == texture_index) {
+            let size = if image.rotated { [image.size[1], image.size[0]] } else { image.size };
+            writeln!(
+                xml,
+                "    <SubTexture name=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" frameX=\"0\" frameY=\"0\" frameWidth=\"{}\" frameHeight=\"{}\" rotated=\"{}\"/>",
+                escape_xml(&image.name),
+                image.position[0],
+                image.position[1],
+                size[0],
+                size[1],
+                image.size[0],
+                image.size[1],
+                image.rotated).unwrap();
+        }
+        xml.push_str("</TextureAtlas>\n");
+        files.push(ExportedFile { filename: page_filename(texture, "xml"), content: xml.into_bytes() });
+    }
+    Ok(files)
+}
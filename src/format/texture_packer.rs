@@ -0,0 +1,62 @@
+use crate::{OutputData, Rotation};
+use super::{check_rotation, check_texture_size, page_filename, ExportedFile};
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramesLayout {
+    Hash,
+    Array,
+}
+
+// TexturePacker json, one file per texture as TexturePacker writes for multipack.
+// sprites are not trimmed, and rotated sprites are rotated 90 degrees clockwise as TexturePacker does.
+pub fn export(data: &OutputData, layout: FramesLayout) -> Result<Vec<ExportedFile>, String> {
+    check_texture_size(data)?;
+    check_rotation(data, Rotation::Clockwise, "texture packer json")?;
+    let page_filenames: Vec<String> = data.textures.iter().map(|a|page_filename(a, "json")).collect();
+    let mut files = Vec::with_capacity(data.textures.len());
+    for (texture_index, texture) in data.textures.iter().enumerate() {
+        let mut frames_hash = Map::new();
+        let mut frames_array = Vec::<Value>::new();
+        for image in data.image_layouts.iter().filter(|a|a.texture == texture_index) {
+            let frame = json!({
+                "frame": { "x": image.position[0], "y": image.position[1], "w": image.size[0], "h": image.size[1] },
+                "rotated": image.rotated,
+                "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": image.size[0], "h": image.size[1] },
+                "sourceSize": { "w": image.size[0], "h": image.size[1] },
+            });
+            match layout {
+                FramesLayout::Hash => {
+                    frames_hash.insert(image.name.clone(), frame);
+                },
+                FramesLayout::Array => {
+                    let mut frame_with_name = Map::new();
+                    frame_with_name.insert(String::from("filename"), Value::from(image.name.clone()));
+                    frame_with_name.extend(frame.as_object().unwrap().clone());
+                    frames_array.push(Value::Object(frame_with_name));
+                },
+            }
+        }
+
+        let related_multi_packs: Vec<&String> = page_filenames.iter().enumerate().filter(|a|a.0 != texture_index).map(|a|a.1).collect();
+        let document = json!({
+            "frames": match layout {
+                FramesLayout::Hash => Value::Object(frames_hash),
+                FramesLayout::Array => Value::Array(frames_array),
+            },
+            "meta": {
+                "app": "image-packer",
+                "version": env!("CARGO_PKG_VERSION"),
+                "image": texture,
+                "format": "RGBA8888",
+                "size": { "w": data.texture_size[0], "h": data.texture_size[1] },
+                "scale": "1",
+                "related_multi_packs": related_multi_packs,
+            },
+        });
+        let content = serde_json::to_vec_pretty(&document).map_err(|e|format!("serialize texture packer json failed. {}", e))?;
+        files.push(ExportedFile { filename: page_filenames[texture_index].clone(), content });
+    }
+    Ok(files)
+}
@@ -0,0 +1,221 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+pub struct InputImage {
+    pub path: PathBuf,
+    pub name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct InputOptions {
+    pub recursive: bool,
+    pub filename_pattern: Option<Regex>,
+    // accept files without known image extensions in directories and glob matches
+    pub any_extension: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Inputs {
+    pub images: Vec<InputImage>,
+    // files left out because of their extensions
+    pub skipped: Vec<PathBuf>,
+}
+
+pub fn has_image_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(image::ImageFormat::from_extension)
+        .is_some()
+}
+
+// Collect files in the directory. names are relative paths from the directory separated by '/'.
+// The pattern is matched against file names. Symbolic links to directories are skipped.
+pub fn collect_directory(
+    dir: &Path,
+    options: &InputOptions,
+) -> Result<Inputs, String> {
+    let mut paths = Vec::<PathBuf>::new();
+    collect_paths(dir, options, &mut paths)?;
+    paths.sort();
+
+    let mut inputs = Inputs::default();
+    for path in paths {
+        if options.any_extension || has_image_extension(&path) {
+            let name = relative_name(dir, &path)?;
+            inputs.images.push(InputImage { path, name });
+        } else {
+            inputs.skipped.push(path);
+        }
+    }
+    Ok(inputs)
+}
+
+fn collect_paths(
+    dir: &Path,
+    options: &InputOptions,
+    paths: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e|format!("read_dir failed. {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e|format!("read_dir failed. {}: {}", dir.display(), e))?;
+        let file_type = entry.file_type().map_err(|e|format!("file_type failed. {}: {}", entry.path().display(), e))?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            if options.recursive {
+                collect_paths(&path, options, paths)?;
+            }
+        } else if path.is_dir() {
+            // links to directories are not followed, they may lead back to an ancestor
+            continue;
+        } else if let Some(ref regex) = options.filename_pattern {
+            if regex.is_match(file_name(&path)?) {
+                paths.push(path);
+            }
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Collect input images from sources. a source is one of
+// - directory: files in it, filtered by the pattern and named relatively from the directory
+// - glob pattern: matched files, filtered by the pattern and named relatively from the directory part without wildcards.
+//   a path which exists is taken literally even if it contains wildcard characters, and a pattern matching no files is an error
+// - @filelist: sources listed line by line, relative paths are resolved from the list location
+// - file: the file named by its file name. it is taken whatever its extension or file name is
+// A file reached by several sources is packed once, and it is an error when the sources give it different names.
+pub fn collect_inputs(
+    sources: &[String],
+    options: &InputOptions,
+) -> Result<Inputs, String> {
+    let mut inputs = Inputs::default();
+    for source in sources {
+        collect_source(source, Path::new(""), options, &mut inputs)?;
+    }
+
+    // the same file given by several sources is packed once
+    let mut seen = HashMap::<PathBuf, usize>::with_capacity(inputs.images.len());
+    let mut images = Vec::<InputImage>::with_capacity(inputs.images.len());
+    for image in inputs.images {
+        let canonical = std::fs::canonicalize(&image.path).map_err(|e|format!("canonicalize failed. {}: {}", image.path.display(), e))?;
+        if let Some(index) = seen.get(&canonical) {
+            let other = &images[*index];
+            if other.name != image.name {
+                return Err(format!("{} is given with different names {} and {}", image.path.display(), other.name, image.name));
+            }
+        } else {
+            seen.insert(canonical, images.len());
+            images.push(image);
+        }
+    }
+    inputs.images = images;
+    let mut seen = HashSet::<PathBuf>::new();
+    inputs.skipped.retain(|a|seen.insert(a.clone()));
+    Ok(inputs)
+}
+
+fn collect_source(
+    source: &str,
+    base: &Path,
+    options: &InputOptions,
+    inputs: &mut Inputs,
+) -> Result<(), String> {
+    if let Some(list) = source.strip_prefix('@') {
+        let list_path = base.join(list);
+        let content = std::fs::read_to_string(&list_path).map_err(|e|format!("read file list failed. {}: {}", list_path.display(), e))?;
+        let list_dir = list_path.parent().unwrap_or_else(||Path::new(""));
+        for line in content.lines().map(str::trim).filter(|a|!a.is_empty() && !a.starts_with('#')) {
+            collect_source(line, list_dir, options, inputs)?;
+        }
+    } else if is_glob_pattern(source) && !base.join(source).exists() {
+        let mut collected = collect_glob(&base.join(source), options)?;
+        inputs.images.append(&mut collected.images);
+        inputs.skipped.append(&mut collected.skipped);
+    } else {
+        let path = base.join(source);
+        if path.is_dir() {
+            let mut collected = collect_directory(&path, options)?;
+            inputs.images.append(&mut collected.images);
+            inputs.skipped.append(&mut collected.skipped);
+        } else if path.is_file() {
+            let name = String::from(file_name(&path)?);
+            inputs.images.push(InputImage { path, name });
+        } else {
+            return Err(format!("input not found. {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+fn is_glob_pattern(source: &str) -> bool {
+    source.contains(['*', '?', '['])
+}
+
+fn collect_glob(pattern: &Path, options: &InputOptions) -> Result<Inputs, String> {
+    let pattern_str = pattern.to_str().ok_or_else(||format!("OsStr::to_str failed. {}", pattern.display()))?;
+    let base: PathBuf = pattern.components()
+        .take_while(|a|!a.as_os_str().to_str().is_some_and(is_glob_pattern))
+        .collect();
+    // '*' stays in a directory, '**' matches subdirectories
+    let match_options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    let mut paths = Vec::<PathBuf>::new();
+    for entry in glob::glob_with(pattern_str, match_options).map_err(|e|format!("bad glob pattern. {}: {}", pattern_str, e))? {
+        let path = entry.map_err(|e|format!("glob failed. {}", e))?;
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(ref regex) = options.filename_pattern {
+            if !regex.is_match(file_name(&path)?) {
+                continue;
+            }
+        }
+        paths.push(path);
+    }
+    if paths.is_empty() {
+        return Err(format!("no files match glob pattern. {}", pattern_str));
+    }
+    paths.sort();
+
+    let mut inputs = Inputs::default();
+    for path in paths {
+        if options.any_extension || has_image_extension(&path) {
+            let name = relative_name(&base, &path)?;
+            inputs.images.push(InputImage { path, name });
+        } else {
+            inputs.skipped.push(path);
+        }
+    }
+    Ok(inputs)
+}
+
+pub fn file_name(path: &Path) -> Result<&str, String> {
+    path.file_name()
+        .ok_or_else(||format!("file_name empty. {}", path.display()))?
+        .to_str()
+        .ok_or_else(||format!("OsStr::to_str failed. {}", path.display()))
+}
+
+pub fn relative_name(base: &Path, path: &Path) -> Result<String, String> {
+    let relative = path.strip_prefix(base).map_err(|_|format!("{} is not under {}", path.display(), base.display()))?;
+    let mut components = Vec::<&str>::new();
+    for component in relative.components() {
+        components.push(component.as_os_str().to_str().ok_or_else(||format!("OsStr::to_str failed. {}", path.display()))?);
+    }
+    Ok(components.join("/"))
+}
+
+pub fn check_duplicate_names(images: &[InputImage]) -> Result<(), String> {
+    let mut names = HashMap::<&str, &Path>::with_capacity(images.len());
+    for image in images {
+        if let Some(other) = names.insert(&image.name, &image.path) {
+            return Err(format!("duplicate image name {}. {} and {}", image.name, other.display(), image.path.display()));
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,15 @@
+pub mod allocator;
+pub mod cache;
+pub mod config;
+pub mod format;
+pub mod input;
+pub mod overlay;
 pub mod packer;
+pub mod report;
+pub use crate::allocator::*;
 pub use crate::packer::*;
 use serde::{Serialize, Deserialize};
+use std::fmt;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ImageLayoutInfo {
@@ -23,8 +32,38 @@ impl ImageLayoutInfo {
     }
 }
 
+// Direction in which rotated sprites are turned 90 degrees to be placed in textures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rotation {
+    #[default]
+    Clockwise,
+    CounterClockwise,
+}
+
+impl Rotation {
+    pub fn is_clockwise(&self) -> bool {
+        *self == Rotation::Clockwise
+    }
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rotation::Clockwise => f.write_str("clockwise"),
+            Rotation::CounterClockwise => f.write_str("counter clockwise"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OutputData {
     pub image_layouts: Vec<ImageLayoutInfo>,
     pub textures: Vec<String>,
+    #[serde(default)]
+    pub texture_size: [usize; 2],
+    #[serde(default, skip_serializing_if = "Rotation::is_clockwise")]
+    pub rotation: Rotation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<Vec<TextureStatistics>>,
 }
\ No newline at end of file
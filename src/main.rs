@@ -1,69 +1,189 @@
-use image::{GenericImage, ImageFormat, ImageBuffer, Rgba};
+use image::{imageops, GenericImage, ImageFormat, RgbaImage};
 use image_packer::*;
 use regex::Regex;
-use std::fs::File;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+// changes are gathered until inputs stay unchanged for this duration
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum DecodeErrorPolicy {
+    Error,
+    Warn,
+    Skip,
+}
+
+impl FromStr for DecodeErrorPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(DecodeErrorPolicy::Error),
+            "warn" => Ok(DecodeErrorPolicy::Warn),
+            "skip" => Ok(DecodeErrorPolicy::Skip),
+            _ => Err(format!("unknown decode error policy. {}", s)),
+        }
+    }
+}
 
 #[derive(Debug)]
 struct Args {
+    jobs: Option<usize>,
+    textures_in_flight: Option<usize>,
+    force: bool,
+    watch: bool,
+    dry_run: bool,
+    project_path: Option<String>,
+    overrides: config::AtlasConfig,
+}
+
+// atlas build settings with defaults applied
+#[derive(Debug, Serialize)]
+struct AtlasSettings {
+    name: Option<String>,
+    input_paths: Vec<String>,
+    output_path: String,
     texture_size: [usize; 2],
     prefix: String,
     spacing: usize,
     enable_rotate: bool,
+    rotation: Rotation,
     input_filename_pattern: Option<String>,
+    recursive: bool,
+    any_extension: bool,
+    on_decode_error: DecodeErrorPolicy,
     output_data_filename: String,
-    input_path: String,
-    output_path: String,
+    data_formats: Vec<format::DataFormat>,
+    output_statistics: bool,
+    debug_overlay: bool,
+    html_report_filename: Option<String>,
+}
+
+fn atlas_args() -> Vec<clap::Arg<'static>> {
+    vec![
+        clap::Arg::new("texture-size")
+            .long("texture-size")
+            .short('s')
+            .value_delimiter(',')
+            .number_of_values(2),
+        clap::Arg::new("texture-prefix")
+            .long("texture-prefix")
+            .takes_value(true),
+        clap::Arg::new("spacing")
+            .long("spacing")
+            .takes_value(true),
+        clap::Arg::new("enable-rotate")
+            .long("enable-rotate")
+            .takes_value(false)
+            .help("allow sprites to be rotated 90 degrees. they are rotated clockwise, \
+                or counter clockwise when libgdx is one of the data formats. \
+                godot, css and scss cannot describe rotated sprites, \
+                and libgdx cannot be combined with texture packer, cocos2d or starling formats"),
+        clap::Arg::new("disable-rotate")
+            .long("disable-rotate")
+            .takes_value(false),
+        clap::Arg::new("input-filename-pattern")
+            .long("input-filename-pattern")
+            .short('p')
+            .takes_value(true),
+        clap::Arg::new("recursive")
+            .long("recursive")
+            .short('r')
+            .takes_value(false),
+        clap::Arg::new("no-recursive")
+            .long("no-recursive")
+            .takes_value(false),
+        clap::Arg::new("any-extension")
+            .long("any-extension")
+            .takes_value(false),
+        clap::Arg::new("no-any-extension")
+            .long("no-any-extension")
+            .takes_value(false),
+        clap::Arg::new("on-decode-error")
+            .long("on-decode-error")
+            .takes_value(true)
+            .possible_values(["error", "warn", "skip"]),
+        clap::Arg::new("output-data-filename")
+            .long("output-data-filename")
+            .takes_value(true),
+        clap::Arg::new("data-format")
+            .long("data-format")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .use_value_delimiter(true)
+            .possible_values(["information", "texture-packer-hash", "texture-packer-array", "libgdx", "godot", "cocos2d-plist", "cocos2d-plist-v3", "starling", "css", "scss", "rust", "c-header", "binary"]),
+        clap::Arg::new("output-statistics")
+            .long("output-statistics")
+            .takes_value(false),
+        clap::Arg::new("no-output-statistics")
+            .long("no-output-statistics")
+            .takes_value(false),
+        clap::Arg::new("debug-overlay")
+            .long("debug-overlay")
+            .takes_value(false),
+        clap::Arg::new("no-debug-overlay")
+            .long("no-debug-overlay")
+            .takes_value(false),
+        clap::Arg::new("html-report")
+            .long("html-report")
+            .takes_value(false),
+        clap::Arg::new("no-html-report")
+            .long("no-html-report")
+            .takes_value(false),
+        clap::Arg::new("html-report-filename")
+            .long("html-report-filename")
+            .takes_value(true),
+        clap::Arg::new("split-subdirectories")
+            .long("split-subdirectories")
+            .takes_value(false),
+        clap::Arg::new("no-split-subdirectories")
+            .long("no-split-subdirectories")
+            .takes_value(false),
+        clap::Arg::new("force")
+            .long("force")
+            .takes_value(false),
+        clap::Arg::new("watch")
+            .long("watch")
+            .takes_value(false),
+        clap::Arg::new("dry-run")
+            .long("dry-run")
+            .takes_value(false),
+        clap::Arg::new("jobs")
+            .long("jobs")
+            .short('j')
+            .takes_value(true)
+            .help("number of worker threads. defaults to the number of cpus"),
+        clap::Arg::new("textures-in-flight")
+            .long("textures-in-flight")
+            .takes_value(true)
+            .help("number of textures decoded and composed at a time. defaults to 1. \
+                each texture holds its decoded sprites and encoded png in memory, \
+                so peak memory grows with this number"),
+    ]
 }
 
 impl Args {
     fn parse() -> Result<Args> {
         let matches = clap::Command::new("image-packer")
             .about("pack input images into textures")
-            .arg(
-                clap::Arg::new("texture-size")
-                    .long("texture-size")
-                    .short('s')
-                    .value_delimiter(',')
-                    .number_of_values(2)
-            )
-            .arg(
-                clap::Arg::new("texture-prefix")
-                    .long("texture-prefix")
-                    .takes_value(true)
-            )
-            .arg(
-                clap::Arg::new("spacing")
-                    .long("spacing")
-                    .takes_value(true)
-            )
-            .arg(
-                clap::Arg::new("enable-rotate")
-                    .long("enable-rotate")
-                    .takes_value(false)
-            )
-            .arg(
-                clap::Arg::new("disable-rotate")
-                    .long("disable-rotate")
-                    .takes_value(false)
-            )
-            .arg(
-                clap::Arg::new("input-filename-pattern")
-                    .long("input-filename-pattern")
-                    .short('p')
-                    .takes_value(true)
-            )
-            .arg(
-                clap::Arg::new("output-data-filename")
-                    .long("output-data-filename")
-                    .takes_value(true)
-            )
+            .args_conflicts_with_subcommands(true)
+            .subcommand_negates_reqs(true)
+            .args(atlas_args())
             .arg(
                 clap::Arg::new("input-path")
                     .takes_value(true)
+                    .multiple_values(true)
                     .required(true)
             )
             .arg(
@@ -71,68 +191,521 @@ impl Args {
                     .takes_value(true)
                     .required(true)
             )
+            .subcommand(
+                clap::Command::new("build")
+                    .about("build atlases described in a project file. options override values in the file")
+                    .args(atlas_args())
+                    .arg(
+                        clap::Arg::new("project-path")
+                            .takes_value(true)
+                            .required(true)
+                    )
+            )
             .get_matches();
 
-        let texture_size: [usize; 2] = if let Some(mut option) = matches.values_of("texture-size") {
+        // positional inputs and output are given in the file in build mode
+        let (matches, project_path, input_paths, output_path) = match matches.subcommand_matches("build") {
+            Some(build_matches) => (build_matches, build_matches.value_of("project-path").map(String::from), None, None),
+            None => (
+                &matches,
+                None,
+                matches.values_of("input-path").map(|a|a.map(String::from).collect()),
+                matches.value_of("output-path").map(String::from),
+            ),
+        };
+
+        let texture_size = if let Some(mut option) = matches.values_of("texture-size") {
             let w = option.next().unwrap().parse::<usize>()?;
             let h = option.next().unwrap().parse::<usize>()?;
-            if w > MAX_TEXTURE_SIZE || h > MAX_TEXTURE_SIZE {
-                return Err(From::from(format!("texture size is too large. ({}, {})", w, h)));
-            }
-            [w, h]
+            Some([w, h])
+        } else {
+            None
+        };
+
+        let enable_rotate = if matches.is_present("disable-rotate") {
+            Some(false)
+        } else if matches.is_present("enable-rotate") {
+            Some(true)
         } else {
-            [1024, 1024]
+            None
+        };
+
+        // --no-<name> turns off a flag set in the project file
+        let flag = |name: &str| {
+            if matches.is_present(format!("no-{}", name)) {
+                Some(false)
+            } else if matches.is_present(name) {
+                Some(true)
+            } else {
+                None
+            }
         };
 
         Ok(Args {
+            jobs: matches.value_of("jobs").map(usize::from_str).transpose()?,
+            textures_in_flight: matches.value_of("textures-in-flight").map(usize::from_str).transpose()?,
+            force: matches.is_present("force"),
+            watch: matches.is_present("watch"),
+            dry_run: matches.is_present("dry-run"),
+            project_path,
+            overrides: config::AtlasConfig {
+                name: None,
+                input_paths,
+                output_path,
+                texture_size,
+                texture_prefix: matches.value_of("texture-prefix").map(String::from),
+                spacing: matches.value_of("spacing").map(usize::from_str).transpose()?,
+                enable_rotate,
+                input_filename_pattern: matches.value_of("input-filename-pattern").map(String::from),
+                recursive: flag("recursive"),
+                any_extension: flag("any-extension"),
+                on_decode_error: matches.value_of("on-decode-error").map(String::from),
+                output_data_filename: matches.value_of("output-data-filename").map(String::from),
+                data_formats: matches.values_of("data-format").map(|a|a.map(String::from).collect()),
+                output_statistics: flag("output-statistics"),
+                debug_overlay: flag("debug-overlay"),
+                html_report: flag("html-report"),
+                html_report_filename: matches.value_of("html-report-filename").map(String::from),
+                split_subdirectories: flag("split-subdirectories"),
+                unknown: Default::default(),
+            },
+        })
+    }
+}
+
+impl AtlasSettings {
+    fn from_config(config: config::AtlasConfig) -> Result<AtlasSettings> {
+        let atlas_name = config.name.as_deref().unwrap_or("atlas");
+        let texture_size = config.texture_size.unwrap_or([1024, 1024]);
+        if texture_size[0] > MAX_TEXTURE_SIZE || texture_size[1] > MAX_TEXTURE_SIZE {
+            return Err(From::from(format!("texture size is too large. ({}, {})", texture_size[0], texture_size[1])));
+        }
+        let enable_rotate = config.enable_rotate.unwrap_or(false);
+        let data_formats: Vec<format::DataFormat> = match config.data_formats {
+            Some(data_formats) => data_formats.iter().map(|a|format::DataFormat::from_str(a)).collect::<std::result::Result<_, _>>()?,
+            None => vec![format::DataFormat::Information],
+        };
+        // choose a rotation direction all data formats can describe.
+        // fail before anything is written rather than when the data file is exported
+        let mut rotations = vec![Rotation::Clockwise, Rotation::CounterClockwise];
+        if enable_rotate {
+            if let Some(data_format) = data_formats.iter().find(|a|a.rotations().is_empty()) {
+                return Err(From::from(format!("{:?} data format does not support rotated sprites. disable rotation for {}", data_format, atlas_name)));
+            }
+            for data_format in data_formats.iter() {
+                rotations.retain(|a|data_format.rotations().contains(a));
+            }
+        }
+        let rotation = *rotations.first().ok_or_else(||format!("data formats {:?} expect different rotation directions. disable rotation for {}", data_formats, atlas_name))?;
+        let html_report_filename = if config.html_report.unwrap_or(false) || config.html_report_filename.is_some() {
+            Some(config.html_report_filename.unwrap_or_else(||String::from("texture-report.html")))
+        } else {
+            None
+        };
+
+        Ok(AtlasSettings {
+            input_paths: config.input_paths.ok_or_else(||format!("input paths are not specified for {}", atlas_name))?,
+            output_path: config.output_path.ok_or_else(||format!("output path is not specified for {}", atlas_name))?,
             texture_size,
-            prefix: matches.value_of("texture-prefix").unwrap_or("texture").to_string(),
-            spacing: matches.value_of("spacing").map_or(Ok(0), usize::from_str)?,
-            enable_rotate: matches.is_present("enable-rotate") && !matches.is_present("disable-rotate"),
-            input_filename_pattern: matches.value_of("input-filename-pattern").map(String::from),
-            output_data_filename: matches.value_of("output-data-filename").unwrap_or("texture-information.json").to_string(),
-            input_path: matches.value_of("input-path").unwrap().to_string(),
-            output_path: matches.value_of("output-path").unwrap().to_string(),
+            prefix: config.texture_prefix.unwrap_or_else(||String::from("texture")),
+            spacing: config.spacing.unwrap_or(0),
+            enable_rotate,
+            rotation,
+            input_filename_pattern: config.input_filename_pattern,
+            recursive: config.recursive.unwrap_or(false),
+            any_extension: config.any_extension.unwrap_or(false),
+            on_decode_error: config.on_decode_error.as_deref().map_or(Ok(DecodeErrorPolicy::Error), DecodeErrorPolicy::from_str)?,
+            output_data_filename: config.output_data_filename.unwrap_or_else(||String::from("texture-information.json")),
+            data_formats,
+            output_statistics: config.output_statistics.unwrap_or(false),
+            debug_overlay: config.debug_overlay.unwrap_or(false),
+            html_report_filename,
+            name: config.name,
         })
     }
 }
 
-fn str_to_error(e: &str) -> Box<dyn std::error::Error> {
+fn str_to_error(e: &str) -> Box<dyn std::error::Error + Send + Sync> {
     From::from(String::from(e))
 }
 
+// Apply f to every item using up to `jobs` threads. results are returned in the order of items.
+fn parallel_map<T, R, F>(jobs: usize, items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(usize, &T) -> R + Sync,
+{
+    if jobs <= 1 || items.len() <= 1 {
+        return items.iter().enumerate().map(|(i, a)|f(i, a)).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..jobs.min(items.len())).map(|_| {
+            scope.spawn(|| {
+                let mut results = Vec::new();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= items.len() {
+                        break;
+                    }
+                    results.push((i, f(i, &items[i])));
+                }
+                results
+            })
+        }).collect();
+        handles.into_iter().flat_map(|a|a.join().unwrap()).collect()
+    });
+    results.sort_by_key(|a|a.0);
+    results.into_iter().map(|a|a.1).collect()
+}
+
+fn compose_texture(
+    texture_size: [usize; 2],
+    rotation: Rotation,
+    sprites: &[(&Layout, Arc<RgbaImage>)],
+) -> Result<RgbaImage> {
+    let mut texture = RgbaImage::new(texture_size[0] as u32, texture_size[1] as u32);
+    for (layout, image) in sprites.iter() {
+        let image = image.as_ref();
+        if layout.rotated {
+            let rotated = match rotation {
+                Rotation::Clockwise => imageops::rotate90(image),
+                Rotation::CounterClockwise => imageops::rotate270(image),
+            };
+            texture.copy_from(&rotated, layout.position[0] as u32, layout.position[1] as u32)?;
+        } else {
+            texture.copy_from(image, layout.position[0] as u32, layout.position[1] as u32)?;
+        }
+    }
+    Ok(texture)
+}
+
+fn handle_decode_error(policy: DecodeErrorPolicy, path: &Path, e: &dyn std::fmt::Display) -> Result<()> {
+    match policy {
+        DecodeErrorPolicy::Error => return Err(From::from(format!("decode failed. {}: {}", path.display(), e))),
+        DecodeErrorPolicy::Warn => eprintln!("warning: decode failed. {}: {}", path.display(), e),
+        DecodeErrorPolicy::Skip => {},
+    }
+    Ok(())
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::<u8>::new());
+    image.write_to(&mut bytes, ImageFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+// leave files with the same contents untouched, so that tools watching outputs are not triggered
+fn write_if_changed(path: &Path, content: &[u8]) -> Result<()> {
+    if std::fs::read(path).is_ok_and(|a|a == content) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+fn print_skipped_files(skipped: &[PathBuf], undecodable: &[PathBuf]) {
+    if skipped.is_empty() && undecodable.is_empty() {
+        return;
+    }
+    eprintln!("skipped {} files", skipped.len() + undecodable.len());
+    for path in skipped.iter() {
+        eprintln!("  {} (not an image extension)", path.display());
+    }
+    for path in undecodable.iter() {
+        eprintln!("  {} (decode failed)", path.display());
+    }
+}
+
+fn print_statistics(texture_name: &str, statistics: &TextureStatistics) {
+    println!(
+        "{}: sprites {}, occupancy {:.2}%, used area {}, wasted area {}, largest free rect {}x{}",
+        texture_name,
+        statistics.sprite_count,
+        statistics.occupancy,
+        statistics.used_area,
+        statistics.wasted_area,
+        statistics.largest_free_rect[0],
+        statistics.largest_free_rect[1]);
+}
+
 fn main() -> Result<()> {
     let args = Args::parse()?;
+    if !args.watch {
+        return build(&args, args.force);
+    }
 
-    // find out input image paths
-    let regex_option = args.input_filename_pattern.map_or(Ok(None),|a|Regex::new(&*a).map(Some))?;
-    let mut input_paths = Vec::<PathBuf>::new();
-    let dir = std::fs::read_dir(args.input_path)?;
-    for entry in dir.into_iter() {
-        let path = entry?.path();
-        if !path.is_dir() {
-            if let Some(ref regex) = regex_option {
-                let filename = path.file_name()
-                        .ok_or_else(||str_to_error("file_name empty"))?
-                        .to_str()
-                        .ok_or_else(||str_to_error("OsStr::to_str failed"))?;
-                if regex.is_match(filename) {
-                    input_paths.push(path);
-                }
-            } else {
-                input_paths.push(path);
+    // poll inputs and rebuild when they have been changed and settled down
+    let mut snapshot = watch_snapshot(&args);
+    if let Err(e) = build(&args, args.force) {
+        eprintln!("error: {}", e);
+    }
+    println!("watching for changes");
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        let mut current = watch_snapshot(&args);
+        if current == snapshot {
+            continue;
+        }
+        loop {
+            std::thread::sleep(WATCH_DEBOUNCE);
+            let next = watch_snapshot(&args);
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+        snapshot = current;
+        if let Err(e) = build(&args, false) {
+            eprintln!("error: {}", e);
+        }
+    }
+}
+
+// modification times and sizes of the project file and input images
+type WatchSnapshot = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+fn watch_snapshot(args: &Args) -> WatchSnapshot {
+    let mut paths: Vec<PathBuf> = args.project_path.iter().map(PathBuf::from).collect();
+    if let Ok((_, atlases)) = resolve_atlases(args) {
+        paths.extend(atlases.into_iter().flat_map(|(_, inputs)|inputs.images.into_iter().map(|a|a.path)));
+    }
+    paths.into_iter().map(|path| {
+        match std::fs::metadata(&path) {
+            Ok(metadata) => (path, metadata.modified().ok(), metadata.len()),
+            Err(_) => (path, None, 0),
+        }
+    }).collect()
+}
+
+fn build(args: &Args, force: bool) -> Result<()> {
+    let (parallelism, atlases) = resolve_atlases(args)?;
+    if args.dry_run {
+        let mut oversized_count = 0;
+        for (settings, inputs) in atlases {
+            if let Some(ref name) = settings.name {
+                println!("{}:", name);
+            }
+            oversized_count += dry_run_atlas(&settings, inputs, parallelism.jobs)?;
+        }
+        if oversized_count > 0 {
+            return Err(From::from(format!("{} images are larger than the texture size", oversized_count)));
+        }
+        return Ok(());
+    }
+
+    let decode_cache = cache::DecodeCache::new(atlases.iter().flat_map(|(_, inputs)|inputs.images.iter().map(|a|a.path.as_path())));
+    let mut output_files = OutputFiles::default();
+    for (settings, inputs) in atlases {
+        if let Some(ref name) = settings.name {
+            println!("{}:", name);
+        }
+        build_atlas(&settings, inputs, &decode_cache, &mut output_files, parallelism, force)?;
+    }
+    Ok(())
+}
+
+// worker threads, and textures composed at a time
+#[derive(Debug, Clone, Copy)]
+struct Parallelism {
+    jobs: usize,
+    textures: usize,
+}
+
+// Files written by the atlases of a build, so that an atlas does not overwrite outputs of another
+#[derive(Default)]
+struct OutputFiles {
+    atlases: HashMap<PathBuf, String>,
+}
+
+impl OutputFiles {
+    // the output directory must exist
+    fn claim<'a>(&mut self, atlas_name: &str, output_dir: &Path, filenames: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let output_dir = std::fs::canonicalize(output_dir)?;
+        for filename in filenames {
+            if let Some(other) = self.atlases.insert(output_dir.join(filename), String::from(atlas_name)) {
+                return Err(From::from(format!("output file name conflicts. {} is written by {} and {}", output_dir.join(filename).display(), other, atlas_name)));
             }
         }
+        Ok(())
     }
-    input_paths.sort();
+}
+
+// resolve all atlases first so that images shared between them are decoded once
+fn resolve_atlases(args: &Args) -> Result<(Parallelism, Vec<(AtlasSettings, input::Inputs)>)> {
+    let (jobs, textures, atlas_configs) = match args.project_path {
+        Some(ref project_path) => {
+            let project = config::ProjectConfig::load(Path::new(project_path))?;
+            (args.jobs.or(project.jobs), args.textures_in_flight.or(project.textures_in_flight), project.atlas_configs())
+        },
+        None => (args.jobs, args.textures_in_flight, vec![config::AtlasConfig::default()]),
+    };
+    if jobs == Some(0) {
+        return Err(str_to_error("jobs must be larger than 0"));
+    }
+    if textures == Some(0) {
+        return Err(str_to_error("textures in flight must be larger than 0"));
+    }
+    // peak memory is a single texture and its sprites unless more textures in flight are given
+    let parallelism = Parallelism {
+        jobs: jobs.unwrap_or_else(||std::thread::available_parallelism().map_or(1, |a|a.get())),
+        textures: textures.unwrap_or(1),
+    };
 
-    // load input images
-    let mut images = Vec::<image::ImageBuffer<Rgba<u8>, _>>::new();
-    let mut image_sizes = Vec::<[usize; 2]>::new();
-    for path in input_paths.iter() {
-        let image = image::open(path)?.to_rgba8();
-        image_sizes.push([image.width() as usize, image.height() as usize]);
-        images.push(image);
+    let mut atlases = Vec::<(AtlasSettings, input::Inputs)>::new();
+    for atlas_config in atlas_configs {
+        for atlas_config in atlas_config.merge(args.overrides.clone()).split_subdirectories()? {
+            let settings = AtlasSettings::from_config(atlas_config)?;
+            // other outputs are checked by OutputFiles when they are written
+            if let Some((other, _)) = atlases.iter().find(|(a, _)|Path::new(&a.output_path) == Path::new(&settings.output_path) && a.prefix == settings.prefix) {
+                return Err(From::from(format!(
+                    "atlases {} and {} have the same texture prefix {} in {}",
+                    other.name.as_deref().unwrap_or("atlas"),
+                    settings.name.as_deref().unwrap_or("atlas"),
+                    settings.prefix,
+                    settings.output_path)));
+            }
+            let inputs = collect_atlas_inputs(&settings)?;
+            atlases.push((settings, inputs));
+        }
+    }
+    Ok((parallelism, atlases))
+}
+
+// find out input images
+fn collect_atlas_inputs(args: &AtlasSettings) -> Result<input::Inputs> {
+    let input_options = input::InputOptions {
+        recursive: args.recursive,
+        filename_pattern: args.input_filename_pattern.as_ref().map_or(Ok(None),|a|Regex::new(a).map(Some))?,
+        any_extension: args.any_extension,
+    };
+    let inputs = input::collect_inputs(&args.input_paths, &input_options)?;
+    input::check_duplicate_names(&inputs.images)?;
+    Ok(inputs)
+}
+
+// input images readable as images, with sizes read from their headers
+struct ProbedImages {
+    paths: Vec<PathBuf>,
+    names: Vec<String>,
+    sizes: Vec<[usize; 2]>,
+    undecodable: Vec<PathBuf>,
+}
+
+fn probe_images(args: &AtlasSettings, images: &[input::InputImage], jobs: usize) -> Result<ProbedImages> {
+    let probed = parallel_map(jobs, images, |_, input|image::image_dimensions(&input.path));
+    let mut result = ProbedImages {
+        paths: Vec::with_capacity(probed.len()),
+        names: Vec::with_capacity(probed.len()),
+        sizes: Vec::with_capacity(probed.len()),
+        undecodable: Vec::new(),
+    };
+    for (input, dimensions) in images.iter().zip(probed) {
+        match dimensions {
+            Ok((width, height)) => {
+                result.paths.push(input.path.clone());
+                result.names.push(input.name.clone());
+                result.sizes.push([width as usize, height as usize]);
+            },
+            Err(e) => {
+                handle_decode_error(args.on_decode_error, &input.path, &e)?;
+                result.undecodable.push(input.path.clone());
+            },
+        }
+    }
+    Ok(result)
+}
+
+// pack with sizes read from image headers and report the result without writing anything.
+// returns the number of images larger than the texture size
+fn dry_run_atlas(
+    args: &AtlasSettings,
+    inputs: input::Inputs,
+    jobs: usize,
+) -> Result<usize> {
+    let probed = probe_images(args, &inputs.images, jobs)?;
+    print_skipped_files(&inputs.skipped, &probed.undecodable);
+    let packer = Packer {
+        texture_size: args.texture_size,
+        spacing: args.spacing,
+        enable_rotate: args.enable_rotate,
+    };
+    let mut image_sizes = Vec::<[usize; 2]>::with_capacity(probed.sizes.len());
+    let mut oversized = Vec::<(&Path, [usize; 2])>::new();
+    for (path, size) in probed.paths.iter().zip(probed.sizes.iter()) {
+        if packer.is_oversized(*size) {
+            oversized.push((path, *size));
+        } else {
+            image_sizes.push(*size);
+        }
+    }
+
+    let packed_results = packer.pack_with_statistics(&image_sizes)?;
+    for (texture_index, packed) in packed_results.iter().enumerate() {
+        print_statistics(&format!("{}{:03}.png", args.prefix, texture_index), &packed.statistics);
+    }
+    let used_area: usize = packed_results.iter().map(|a|a.statistics.used_area).sum();
+    let total_area = packed_results.len() * args.texture_size[0] * args.texture_size[1];
+    println!(
+        "{} textures, {} images, occupancy {:.2}%",
+        packed_results.len(),
+        image_sizes.len(),
+        if total_area == 0 { 0.0 } else { used_area as f64 * 100.0 / total_area as f64 });
+
+    if !oversized.is_empty() {
+        eprintln!("{} images are larger than the texture size {}x{}", oversized.len(), args.texture_size[0], args.texture_size[1]);
+        for (path, size) in oversized.iter() {
+            eprintln!("  {} ({}x{})", path.display(), size[0], size[1]);
+        }
+    }
+    Ok(oversized.len())
+}
+
+fn build_atlas(
+    args: &AtlasSettings,
+    inputs: input::Inputs,
+    decode_cache: &cache::DecodeCache,
+    output_files: &mut OutputFiles,
+    parallelism: Parallelism,
+    force: bool,
+) -> Result<()> {
+    let jobs = parallelism.jobs;
+    let atlas_name = args.name.as_deref().unwrap_or("atlas");
+    // skip the build when inputs and options are unchanged since the previous build
+    let output_dir = Path::new(&args.output_path);
+    let manifest_filename = cache::manifest_filename(&args.prefix);
+    let manifest_path = output_dir.join(&manifest_filename);
+    let hashes = parallel_map(jobs, &inputs.images, |_, input|cache::file_hash(&input.path)).into_iter().collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut manifest = cache::BuildManifest {
+        version: String::from(env!("CARGO_PKG_VERSION")),
+        options: serde_json::to_value(args)?,
+        inputs: inputs.images.iter().zip(hashes).map(|(input, hash)| {
+            cache::ManifestInput {
+                name: input.name.clone(),
+                path: input.path.display().to_string(),
+                hash,
+            }
+        }).collect(),
+        outputs: Vec::new(),
+    };
+    if let Some(previous) = cache::BuildManifest::load(&manifest_path).filter(|a|!force && manifest.is_up_to_date(a, output_dir)) {
+        output_files.claim(atlas_name, output_dir, previous.outputs.iter().map(String::as_str).chain([manifest_filename.as_str()]))?;
+        for input in inputs.images.iter() {
+            decode_cache.release(&input.path);
+        }
+        println!("{}: up to date", args.output_path);
+        return Ok(());
+    }
+
+    // read image sizes from headers. pixels are decoded later texture by texture
+    let mut probed = probe_images(args, &inputs.images, jobs)?;
+    for path in probed.undecodable.iter() {
+        decode_cache.release(path);
     }
 
     // packing
@@ -141,56 +714,133 @@ fn main() -> Result<()> {
         spacing: args.spacing,
         enable_rotate: args.enable_rotate,
     };
-    let packed_results = packer.pack(&image_sizes)?;
+    let packed_results = packer.pack_with_statistics(&probed.sizes)?;
 
     // create output directory if it dose not exist
-    let output_dir = std::path::Path::new(&args.output_path);
     if !output_dir.is_dir() {
-        std::fs::create_dir(output_dir)?;
+        std::fs::create_dir_all(output_dir)?;
     }
+    // outputs are not reusable until the new manifest is written
+    cache::BuildManifest::remove(&manifest_path)?;
+
+    // compose and save textures in chunks, so that only sprites of textures in a chunk are decoded at a time.
+    // sprites of a chunk are decoded in parallel, then its textures are composed and encoded in parallel
+    let texture_names: Vec<String> = (0..packed_results.len()).map(|i|format!("{}{:03}.png", args.prefix, i)).collect();
+    let debug_texture_names: Vec<String> = (0..packed_results.len()).map(|i|format!("{}{:03}-debug.png", args.prefix, i)).collect();
+    output_files.claim(atlas_name, output_dir, texture_names.iter().map(String::as_str).chain([manifest_filename.as_str()]))?;
+    if args.debug_overlay {
+        output_files.claim(atlas_name, output_dir, debug_texture_names.iter().map(String::as_str))?;
+    }
+    let mut failed = vec![false; probed.paths.len()];
+    for (chunk_index, chunk) in packed_results.chunks(parallelism.textures).enumerate() {
+        let layouts: Vec<(usize, &Layout)> = chunk.iter().enumerate().flat_map(|(i, a)|a.layouts.iter().map(move |b|(i, b))).collect();
+        let decoded = parallel_map(jobs, &layouts, |_, (_, layout)| -> Result<Arc<RgbaImage>> {
+            let image = decode_cache.decode(&probed.paths[layout.index])?;
+            let size = probed.sizes[layout.index];
+            if [image.width() as usize, image.height() as usize] != size {
+                return Err(From::from(format!("image size differs from its header. ({}, {})", size[0], size[1])));
+            }
+            Ok(image)
+        });
+        let mut sprites: Vec<Vec<(&Layout, Arc<RgbaImage>)>> = chunk.iter().map(|a|Vec::with_capacity(a.layouts.len())).collect();
+        for ((i, layout), result) in layouts.into_iter().zip(decoded) {
+            match result {
+                Ok(image) => sprites[i].push((layout, image)),
+                Err(e) => {
+                    // the space stays empty and the image is left out of the information json
+                    handle_decode_error(args.on_decode_error, &probed.paths[layout.index], &*e)?;
+                    probed.undecodable.push(probed.paths[layout.index].clone());
+                    failed[layout.index] = true;
+                },
+            }
+        }
+
+        parallel_map(jobs, &sprites, |i, sprites| -> Result<()> {
+            let texture_index = chunk_index * parallelism.textures + i;
+            let texture = compose_texture(packer.texture_size, args.rotation, sprites)?;
+            write_if_changed(&output_dir.join(Path::new(&texture_names[texture_index])), &encode_png(&texture)?)?;
+            if args.debug_overlay {
+                let mut debug_texture = texture;
+                overlay::draw_debug_overlay(&mut debug_texture, &chunk[i], &probed.sizes, &probed.names, &failed, packer.spacing);
+                let debug_texture_path = output_dir.join(Path::new(&debug_texture_names[texture_index]));
+                write_if_changed(&debug_texture_path, &encode_png(&debug_texture)?)?;
+            }
+            Ok(())
+        }).into_iter().collect::<Result<Vec<()>>>()?;
+    }
+    print_skipped_files(&inputs.skipped, &probed.undecodable);
 
     // output result textures and packed information json
     let mut output_data = OutputData {
         textures: Vec::<String>::with_capacity(packed_results.len()),
-        image_layouts: Vec::<ImageLayoutInfo>::with_capacity(input_paths.len()),
+        image_layouts: Vec::<ImageLayoutInfo>::with_capacity(probed.paths.len()),
+        texture_size: args.texture_size,
+        rotation: args.rotation,
+        statistics: None,
     };
-    for _ in 0..input_paths.len() {
+    let mut statistics = Vec::<TextureStatistics>::with_capacity(packed_results.len());
+    for _ in 0..probed.paths.len() {
         output_data.image_layouts.push(ImageLayoutInfo::empty());
     }
-    let mut texture_buffer: Vec<u8> = vec![0; packer.texture_size[0] * packer.texture_size[1] * 4];
-    for (texture_index, layouts) in packed_results.into_iter().enumerate() {
-        let mut texture =  ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(packer.texture_size[0] as u32, packer.texture_size[1] as u32, texture_buffer)
-                .ok_or(str_to_error("textrue initialize error"))?;
-
-        for layout in layouts {
-            texture.copy_from(&images[layout.index], layout.position[0] as u32, layout.position[1] as u32)?;
-            let image_name = input_paths[layout.index]
-                    .file_name()
-                    .ok_or_else(||str_to_error("file_name empty"))?
-                    .to_str()
-                    .ok_or_else(||str_to_error("OsStr::to_str failed"))?;
+    for ((texture_index, packed), texture_name) in packed_results.into_iter().enumerate().zip(texture_names) {
+        for layout in packed.layouts.iter() {
             let image_layout = ImageLayoutInfo {
-                name: String::from(image_name),
+                name: probed.names[layout.index].clone(),
                 texture: texture_index,
                 position: layout.position,
-                size: image_sizes[layout.index],
+                size: probed.sizes[layout.index],
                 rotated: layout.rotated,
             };
             output_data.image_layouts[layout.index] = image_layout;
         }
-
-        let texture_name = format!("{}{:03}.png", args.prefix, texture_index);
-        let texture_path = output_dir.join(Path::new(&texture_name));
-        texture.save_with_format(texture_path, ImageFormat::Png)?;
+        // sprites which failed to decode leave their spaces empty
+        let mut texture_statistics = packed.statistics;
+        for layout in packed.layouts.iter().filter(|a|failed[a.index]) {
+            let area = probed.sizes[layout.index][0] * probed.sizes[layout.index][1];
+            texture_statistics.sprite_count -= 1;
+            texture_statistics.used_area -= area;
+            texture_statistics.wasted_area += area;
+        }
+        texture_statistics.occupancy = texture_statistics.used_area as f64 * 100.0 / (args.texture_size[0] * args.texture_size[1]) as f64;
+        print_statistics(&texture_name, &texture_statistics);
         output_data.textures.push(texture_name);
-        texture_buffer = texture.into_vec();
-        texture_buffer.fill(0);
+        statistics.push(texture_statistics);
     }
+    output_data.image_layouts = output_data.image_layouts.into_iter().zip(failed).filter(|a|!a.1).map(|a|a.0).collect();
 
-    // output json
+    if args.output_statistics {
+        output_data.statistics = Some(statistics);
+    }
+
+    // output packed information files
     output_data.image_layouts.sort_by(|a, b|a.name.cmp(&b.name));
-    let output_data_path = output_dir.join(Path::new(&args.output_data_filename));
-    serde_json::to_writer(File::create(output_data_path)?, &output_data)?;
+    let mut data_filenames = Vec::<String>::new();
+    for data_format in args.data_formats.iter() {
+        for file in format::export(*data_format, &output_data, &args.output_data_filename)? {
+            output_files.claim(atlas_name, output_dir, [file.filename.as_str()])?;
+            write_if_changed(&output_dir.join(Path::new(&file.filename)), &file.content)?;
+            data_filenames.push(file.filename);
+        }
+    }
+
+    // output html report
+    if let Some(ref html_report_filename) = args.html_report_filename {
+        output_files.claim(atlas_name, output_dir, [html_report_filename.as_str()])?;
+        let html_report_path = output_dir.join(Path::new(html_report_filename));
+        let html_report_dir = html_report_path.parent().unwrap_or(output_dir);
+        std::fs::create_dir_all(html_report_dir)?;
+        let texture_dir = report::relative_texture_dir(html_report_dir, output_dir)?;
+        write_if_changed(&html_report_path, report::html_report(&output_data, &texture_dir).as_bytes())?;
+    }
+
+    // record the build so that the next build with the same inputs can be skipped
+    manifest.outputs = output_data.textures.clone();
+    if args.debug_overlay {
+        manifest.outputs.extend(debug_texture_names);
+    }
+    manifest.outputs.extend(data_filenames);
+    manifest.outputs.extend(args.html_report_filename.iter().cloned());
+    manifest.save(&manifest_path)?;
 
     Ok(())
 }
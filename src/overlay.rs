@@ -0,0 +1,192 @@
+use crate::packer::{PackedTexture, Rect};
+use image::RgbaImage;
+
+const OUTLINE_COLORS: [[u8; 3]; 8] = [
+    [255, 64, 64],
+    [64, 255, 64],
+    [64, 128, 255],
+    [255, 255, 64],
+    [255, 64, 255],
+    [64, 255, 255],
+    [255, 160, 32],
+    [160, 96, 255],
+];
+const FREE_SPACE_COLOR: [u8; 4] = [128, 128, 128, 160];
+const SPACING_COLOR: [u8; 4] = [255, 0, 0, 64];
+const LABEL_BACKGROUND_COLOR: [u8; 4] = [0, 0, 0, 192];
+const LABEL_COLOR: [u8; 4] = [255, 255, 255, 255];
+const FAILED_COLOR: [u8; 4] = [96, 96, 96, 255];
+const HATCH_INTERVAL: usize = 6;
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+
+// 5x7 bitmap glyphs. each row uses lower 5 bits, most significant bit is the left column.
+// lowercase letters are drawn with uppercase glyphs and unknown characters with '?'.
+const GLYPHS: [(char, [u8; GLYPH_HEIGHT]); 45] = [
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('A', [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    ('@', [0x0E, 0x11, 0x17, 0x15, 0x17, 0x10, 0x0F]),
+    ('#', [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+];
+
+// Draw sprite outlines, hatched free spaces, shaded spacing gutters and sprite labels over a texture image.
+// sprites which failed to decode are outlined in gray and crossed out, as their spaces are left empty.
+pub fn draw_debug_overlay(
+    image: &mut RgbaImage,
+    packed: &PackedTexture,
+    image_sizes: &[[usize; 2]],
+    names: &[String],
+    failed: &[bool],
+    spacing: usize,
+) {
+    for space in packed.free_spaces.iter() {
+        for y in space.position[1]..(space.position[1] + space.size[1]) {
+            for x in space.position[0]..(space.position[0] + space.size[0]) {
+                if (x + y) % HATCH_INTERVAL == 0 {
+                    blend_pixel(image, x, y, FREE_SPACE_COLOR);
+                }
+            }
+        }
+    }
+
+    for layout in packed.layouts.iter() {
+        let size = image_sizes[layout.index];
+        let size = if layout.rotated { [size[1], size[0]] } else { size };
+        let rect = Rect { position: layout.position, size };
+
+        // spacing gutters at right and bottom side of the sprite
+        if spacing > 0 {
+            let [x, y] = rect.position;
+            let [w, h] = rect.size;
+            fill_rect(image, &Rect { position: [x + w, y], size: [spacing, h + spacing] }, SPACING_COLOR);
+            fill_rect(image, &Rect { position: [x, y + h], size: [w, spacing] }, SPACING_COLOR);
+        }
+
+        if failed.get(layout.index).copied().unwrap_or(false) {
+            draw_outline(image, &rect, FAILED_COLOR);
+            draw_cross(image, &rect, FAILED_COLOR);
+        } else {
+            let [r, g, b] = OUTLINE_COLORS[layout.index % OUTLINE_COLORS.len()];
+            draw_outline(image, &rect, [r, g, b, 255]);
+        }
+
+        let label = format!("#{} {}", layout.index, names.get(layout.index).map_or("", |a|a.as_str()));
+        draw_text(image, &rect, &label);
+    }
+}
+
+fn blend_pixel(image: &mut RgbaImage, x: usize, y: usize, color: [u8; 4]) {
+    if x >= image.width() as usize || y >= image.height() as usize {
+        return;
+    }
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    let alpha = color[3] as u32;
+    for c in 0..3 {
+        pixel[c] = ((color[c] as u32 * alpha + pixel[c] as u32 * (255 - alpha)) / 255) as u8;
+    }
+    pixel[3] = pixel[3].max(color[3]);
+}
+
+fn fill_rect(image: &mut RgbaImage, rect: &Rect, color: [u8; 4]) {
+    for y in rect.position[1]..(rect.position[1] + rect.size[1]) {
+        for x in rect.position[0]..(rect.position[0] + rect.size[0]) {
+            blend_pixel(image, x, y, color);
+        }
+    }
+}
+
+fn draw_outline(image: &mut RgbaImage, rect: &Rect, color: [u8; 4]) {
+    let [x, y] = rect.position;
+    let [w, h] = rect.size;
+    fill_rect(image, &Rect { position: [x, y], size: [w, 1] }, color);
+    fill_rect(image, &Rect { position: [x, y + h - 1], size: [w, 1] }, color);
+    fill_rect(image, &Rect { position: [x, y], size: [1, h] }, color);
+    fill_rect(image, &Rect { position: [x + w - 1, y], size: [1, h] }, color);
+}
+
+// diagonals of the rect, from the top left and from the top right
+fn draw_cross(image: &mut RgbaImage, rect: &Rect, color: [u8; 4]) {
+    let [x, y] = rect.position;
+    let [w, h] = rect.size;
+    for i in 0..w.max(h) {
+        let dx = i * w / w.max(h);
+        let dy = i * h / w.max(h);
+        blend_pixel(image, x + dx, y + dy, color);
+        blend_pixel(image, x + w - 1 - dx, y + dy, color);
+    }
+}
+
+// Draw text from top-left corner inside of the rect. characters overflowing the rect are dropped.
+fn draw_text(image: &mut RgbaImage, rect: &Rect, text: &str) {
+    let advance = GLYPH_WIDTH + 1;
+    let line_height = GLYPH_HEIGHT + 2;
+    if rect.size[0] < advance + 2 || rect.size[1] < line_height + 2 {
+        return;
+    }
+    let max_chars = (rect.size[0] - 2) / advance;
+    let chars: Vec<char> = text.chars().take(max_chars).collect();
+    let [x0, y0] = [rect.position[0] + 1, rect.position[1] + 1];
+    fill_rect(image, &Rect { position: [x0, y0], size: [chars.len() * advance + 1, line_height] }, LABEL_BACKGROUND_COLOR);
+
+    for (i, c) in chars.into_iter().enumerate() {
+        let glyph = find_glyph(c);
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    blend_pixel(image, x0 + 1 + i * advance + column, y0 + 1 + row, LABEL_COLOR);
+                }
+            }
+        }
+    }
+}
+
+fn find_glyph(c: char) -> &'static [u8; GLYPH_HEIGHT] {
+    let c = c.to_ascii_uppercase();
+    GLYPHS.iter()
+        .find(|(a, _)|*a == c)
+        .or_else(||GLYPHS.iter().find(|(a, _)|*a == '?'))
+        .map(|(_, glyph)|glyph)
+        .unwrap()
+}
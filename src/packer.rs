@@ -1,7 +1,10 @@
+use serde::{Serialize, Deserialize};
 use std::collections::btree_map::{BTreeMap};
 use std::ops::Bound::{Included, Unbounded};
 
 pub const MAX_TEXTURE_SIZE: usize = 4096;
+const GRID_DIVISION: usize = 16;
+const MIN_GRID_CELL_SIZE: usize = 8;
 
 #[derive(Debug)]
 pub struct Packer {
@@ -22,22 +25,59 @@ struct Image {
     size: [usize; 2],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Layout {
     pub index: usize,
     pub position: [usize; 2],
     pub rotated: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextureStatistics {
+    pub sprite_count: usize,
+    pub used_area: usize,
+    pub wasted_area: usize,
+    pub occupancy: f64,
+    pub largest_free_rect: [usize; 2],
+}
+
 #[derive(Debug)]
+pub struct PackedTexture {
+    pub layouts: Vec<Layout>,
+    pub statistics: TextureStatistics,
+    pub free_spaces: Vec<Rect>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Rect {
     pub size: [usize; 2],
     pub position: [usize; 2],
 }
 
+// free spaces indexed by area and width for finding space, and by grid cells for intersection queries.
+// spaces are stored in slots, and slots in a group of equal area and width are ordered by insertion.
 #[derive(Debug)]
 pub(crate) struct Spaces {
-    spaces: BTreeMap<usize, BTreeMap<usize, Vec<Rect>>>
+    spaces: BTreeMap<usize, BTreeMap<usize, Vec<usize>>>,
+    slots: Vec<Option<Space>>,
+    free_slots: Vec<usize>,
+    grid: SpaceGrid,
+    next_order: usize,
+    failed_sizes: Vec<[usize; 2]>,
+}
+
+#[derive(Debug)]
+struct Space {
+    rect: Rect,
+    order: usize,
+}
+
+#[derive(Debug)]
+struct SpaceGrid {
+    cell_size: usize,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
 }
 
 impl Rect {
@@ -48,8 +88,8 @@ impl Rect {
         let [rx, ry] = self.position;
         let (cx, cy) = (x * 2 + w, y * 2 + h);
         let (rcx, rcy) = (rx * 2 + rw, ry * 2 + rh);
-        let (dx, dy) = ((if cx >= rcx {cx - rcx} else {rcx - cx}), if cy >= rcy {cy - rcy} else {rcy - cy});
-        return dx < w + rw && dy < h + rh;
+        let (dx, dy) = (cx.abs_diff(rcx), cy.abs_diff(rcy));
+        dx < w + rw && dy < h + rh
     }
 
     pub fn include(&self, other: &Rect) -> bool {
@@ -57,7 +97,7 @@ impl Rect {
         let [x, y] = other.position;
         let [rx, ry] = self.position;
         let [rw, rh] = self.size;
-        return rx <= x && x + w <= rx + rw && ry <= y && y + h <= ry + rh;
+        rx <= x && x + w <= rx + rw && ry <= y && y + h <= ry + rh
     }
 
     pub fn divide(&self, other: &Rect, min_size: usize) -> Vec<Rect> {
@@ -103,62 +143,91 @@ impl Rect {
             }
         }
 
-        if rects.is_empty() && !self.has_intersection(other) {
-            if self.size[0] >= min_size && self.size[1] >= min_size {
-                rects.push(Rect {size: self.size, position: self.position});
-            }
+        if rects.is_empty() && !self.has_intersection(other) && self.size[0] >= min_size && self.size[1] >= min_size {
+            rects.push(Rect {size: self.size, position: self.position});
         }
 
-        return rects;
+        rects
+    }
+
+    // rects covered by the union of two free rects which extend one of them over the other.
+    // side by side rects make a rect over their overlapping rows, stacked ones over their overlapping columns.
+    fn merge(&self, other: &Rect) -> Vec<Rect> {
+        let mut rects: Vec<Rect> = Vec::with_capacity(2);
+        let [x, y] = other.position;
+        let [x1, y1] = [x + other.size[0], y + other.size[1]];
+        let [rx, ry] = self.position;
+        let [rx1, ry1] = [rx + self.size[0], ry + self.size[1]];
+
+        // horizontally touching or overlapping
+        if x <= rx1 && rx <= x1 && ry.max(y) < ry1.min(y1) {
+            let position = [rx.min(x), ry.max(y)];
+            rects.push(Rect { size: [rx1.max(x1) - position[0], ry1.min(y1) - position[1]], position });
+        }
+
+        // vertically touching or overlapping
+        if y <= ry1 && ry <= y1 && rx.max(x) < rx1.min(x1) {
+            let position = [rx.max(x), ry.min(y)];
+            rects.push(Rect { size: [rx1.min(x1) - position[0], ry1.max(y1) - position[1]], position });
+        }
+
+        rects
     }
 }
 
 impl Spaces {
     pub fn new(size: [usize; 2]) -> Spaces {
-        let area = size[0] * size[1];
-        let rect = Rect {
-            size,
-            position: [0, 0],
+        let mut spaces = Spaces {
+            spaces: BTreeMap::new(),
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            grid: SpaceGrid::new(size),
+            next_order: 0,
+            failed_sizes: Vec::new(),
         };
-        return Spaces { spaces: BTreeMap::from([(area, BTreeMap::from([(size[0], Vec::from([rect]))]))]) };
+        spaces.insert(Rect { size, position: [0, 0] });
+        spaces
     }
 
-    pub fn find_space(&self, size: [usize; 2]) -> Option<Rect> {
+    pub fn find_space(&mut self, size: [usize; 2]) -> Option<Rect> {
+        // free spaces only shrink until a release, so a size once failed never fits again
+        if self.failed_sizes.iter().any(|a|a[0] <= size[0] && a[1] <= size[1]) {
+            return None;
+        }
+
         let area = size[0] * size[1];
         for (space_area, spaces_equal_area) in self.spaces.range((Included(area), Unbounded)) {
             if let Some((_, found_spaces)) = spaces_equal_area
                     .range((Included(size[0]), Unbounded))
                     .find(|(space_width, spaces_equal_width)| !spaces_equal_width.is_empty() && (**space_width >= size[0]) && (*space_area >= (size[1] * (**space_width)))) {
-                return Some(Rect{ size:found_spaces[0].size, position: found_spaces[0].position});
+                return Some(self.rect(found_spaces[0]).clone());
             }
         }
-        return None;
+        self.failed_sizes.retain(|a|!(size[0] <= a[0] && size[1] <= a[1]));
+        self.failed_sizes.push(size);
+        None
     }
 
     pub fn exclude(&mut self, other: &Rect, min_size: usize) {
-        let mut divided_spaces: Vec<Rect> = Vec::new();
-        for (_, spaces_equal_area) in self.spaces.iter_mut() {
-            for (_, spaces_equal_width) in spaces_equal_area.into_iter() {
-                let mut remove_indices: Vec<usize> = Vec::new();
-                for (i, space) in spaces_equal_width.into_iter().enumerate() {
-                    if space.has_intersection(other) {
-                        remove_indices.push(i);
-                        divided_spaces.append(&mut space.divide(&Rect{size: other.size, position: other.position}, min_size));
+        // visit intersecting spaces in the same order as iterating by area, width and insertion order
+        let mut intersected: Vec<(usize, usize, usize, usize)> = self.grid.query(other)
+                .into_iter()
+                .filter_map(|slot|{
+                    let space = self.slots[slot].as_ref().unwrap();
+                    if space.rect.has_intersection(other) {
+                        Some((space.rect.size[0] * space.rect.size[1], space.rect.size[0], space.order, slot))
+                    } else {
+                        None
                     }
-                }
+                })
+                .collect();
+        intersected.sort_unstable();
 
-                // remove reversely not to change other indices
-                for i in remove_indices.iter().rev() {
-                    spaces_equal_width.remove(*i);
-                }
-            }
-        }
-
-        // remove empty
-        for (_, spaces_equal_area) in self.spaces.iter_mut() {
-            spaces_equal_area.retain(|_, a|!a.is_empty());
+        let mut divided_spaces: Vec<Rect> = Vec::new();
+        for (_, _, _, slot) in intersected {
+            let space = self.remove(slot);
+            divided_spaces.append(&mut space.divide(other, min_size));
         }
-        self.spaces.retain(|_, a|!a.is_empty());
 
         // sort new divided spaces by area
         divided_spaces.sort_by(|a, b|(b.size[0] * b.size[1]).cmp(&(a.size[0] * a.size[1])));
@@ -169,69 +238,275 @@ impl Spaces {
         }
     }
 
-    pub fn add(&mut self, new_space: Rect) {
-        let area = new_space.size[0] * new_space.size[1];
-        let width = new_space.size[0];
-        for (_, spaces_equal_area) in self.spaces.range((Included(area), Unbounded)) {
-            for (_, spaces_equal_width) in spaces_equal_area.range((Included(width), Unbounded)) {
-                for a in spaces_equal_width {
-                    if a.include(&new_space) {
-                        // other space cover new one
-                        return;
+    // Return a rect which was excluded before to free spaces.
+    // free rects grown from the released one by merging with touching spaces are added,
+    // and spaces covered by them are removed. spaces away from the released rect are untouched.
+    pub fn release(&mut self, released: &Rect, min_size: usize) {
+        // free spaces grow, so sizes failed before may fit now
+        self.failed_sizes.clear();
+
+        let mut candidates: Vec<Rect> = vec![released.clone()];
+        let mut i = 0;
+        while i < candidates.len() {
+            let candidate = candidates[i].clone();
+            i += 1;
+            let [x, y] = [candidate.position[0].saturating_sub(1), candidate.position[1].saturating_sub(1)];
+            let around = Rect {
+                size: [candidate.position[0] + candidate.size[0] + 1 - x, candidate.position[1] + candidate.size[1] + 1 - y],
+                position: [x, y],
+            };
+            for slot in self.grid.query(&around) {
+                for merged in candidate.merge(self.rect(slot)) {
+                    // new free rects include a part of the released rect, others are covered by spaces already
+                    if merged.has_intersection(released)
+                            && !candidates.iter().any(|a|a.include(&merged))
+                            && !self.is_covered(&merged) {
+                        candidates.push(merged);
                     }
                 }
             }
         }
 
-        if let Some(spaces_equal_area) = self.spaces.get_mut(&area) {
-            if let Some(spaces_equal_width) = spaces_equal_area.get_mut(&width) {
-                spaces_equal_width.push(new_space);
-            } else {
-                spaces_equal_area.insert(width, vec![new_space]);
+        candidates.sort_by(|a, b|(b.size[0] * b.size[1]).cmp(&(a.size[0] * a.size[1])));
+        let mut added: Vec<Rect> = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            if candidate.size[0] < min_size || candidate.size[1] < min_size || added.iter().any(|a|a.include(&candidate)) {
+                continue;
             }
+            let covered: Vec<usize> = self.grid.query(&candidate).into_iter().filter(|slot|candidate.include(self.rect(*slot))).collect();
+            for slot in covered {
+                self.remove(slot);
+            }
+            self.insert(candidate.clone());
+            added.push(candidate);
+        }
+    }
+
+    fn is_covered(&self, rect: &Rect) -> bool {
+        // a space covering the rect must also cover its top-left corner
+        let corner = Rect { size: [1, 1], position: rect.position };
+        self.grid.query(&corner).into_iter().any(|slot|self.rect(slot).include(rect))
+    }
+
+    pub fn rects(&self) -> impl Iterator<Item = &Rect> {
+        self.spaces.values().flat_map(|a|a.values()).flatten().map(move |slot|self.rect(*slot))
+    }
+
+    // new space must be inside of a removed space
+    fn add(&mut self, new_space: Rect) {
+        if self.is_covered(&new_space) {
+            // other space cover new one
+            return;
+        }
+        self.insert(new_space);
+    }
+
+    fn rect(&self, slot: usize) -> &Rect {
+        &self.slots[slot].as_ref().unwrap().rect
+    }
+
+    fn insert(&mut self, new_space: Rect) {
+        let area = new_space.size[0] * new_space.size[1];
+        let width = new_space.size[0];
+        let space = Space { rect: new_space, order: self.next_order };
+        self.next_order += 1;
+        let slot = if let Some(slot) = self.free_slots.pop() {
+            self.slots[slot] = Some(space);
+            slot
         } else {
-            self.spaces.insert(area, BTreeMap::from([
-                (width, vec![new_space])
-            ]));
+            self.slots.push(Some(space));
+            self.slots.len() - 1
+        };
+        self.spaces.entry(area).or_default().entry(width).or_default().push(slot);
+        self.grid.insert(slot, &self.slots[slot].as_ref().unwrap().rect);
+    }
+
+    fn remove(&mut self, slot: usize) -> Rect {
+        let rect = self.slots[slot].take().unwrap().rect;
+        self.free_slots.push(slot);
+        let area = rect.size[0] * rect.size[1];
+        let width = rect.size[0];
+        let spaces_equal_area = self.spaces.get_mut(&area).unwrap();
+        let spaces_equal_width = spaces_equal_area.get_mut(&width).unwrap();
+        let i = spaces_equal_width.iter().position(|a|*a == slot).unwrap();
+        spaces_equal_width.remove(i);
+
+        // remove empty
+        if spaces_equal_width.is_empty() {
+            spaces_equal_area.remove(&width);
+            if spaces_equal_area.is_empty() {
+                self.spaces.remove(&area);
+            }
         }
+        self.grid.remove(slot, &rect);
+        rect
+    }
+}
+
+impl SpaceGrid {
+    fn new(size: [usize; 2]) -> SpaceGrid {
+        let cell_size = size[0].max(size[1]).div_ceil(GRID_DIVISION).max(MIN_GRID_CELL_SIZE);
+        let columns = size[0].div_ceil(cell_size);
+        let rows = size[1].div_ceil(cell_size);
+        SpaceGrid { cell_size, columns, rows, cells: vec![Vec::new(); columns * rows] }
+    }
+
+    fn cell_range(&self, rect: &Rect) -> ([usize; 2], [usize; 2]) {
+        let last_column = self.columns - 1;
+        let last_row = self.rows - 1;
+        let x0 = (rect.position[0] / self.cell_size).min(last_column);
+        let y0 = (rect.position[1] / self.cell_size).min(last_row);
+        let x1 = ((rect.position[0] + rect.size[0].max(1) - 1) / self.cell_size).min(last_column);
+        let y1 = ((rect.position[1] + rect.size[1].max(1) - 1) / self.cell_size).min(last_row);
+        ([x0, y0], [x1, y1])
+    }
+
+    fn insert(&mut self, slot: usize, rect: &Rect) {
+        let ([x0, y0], [x1, y1]) = self.cell_range(rect);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.cells[y * self.columns + x].push(slot);
+            }
+        }
+    }
+
+    fn remove(&mut self, slot: usize, rect: &Rect) {
+        let ([x0, y0], [x1, y1]) = self.cell_range(rect);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let cell = &mut self.cells[y * self.columns + x];
+                if let Some(i) = cell.iter().position(|a|*a == slot) {
+                    cell.swap_remove(i);
+                }
+            }
+        }
+    }
+
+    // slots of spaces which may intersect with the rect
+    fn query(&self, rect: &Rect) -> Vec<usize> {
+        let ([x0, y0], [x1, y1]) = self.cell_range(rect);
+        let mut slots: Vec<usize> = Vec::new();
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                slots.extend_from_slice(&self.cells[y * self.columns + x]);
+            }
+        }
+        if x0 != x1 || y0 != y1 {
+            slots.sort_unstable();
+            slots.dedup();
+        }
+        slots
     }
 }
 
 impl Packed {
     pub fn new(texture_size: [usize; 2]) -> Packed {
-        return Packed { layouts: Vec::new(), spaces: Spaces::new(texture_size) };
+        Packed { layouts: Vec::new(), spaces: Spaces::new(texture_size) }
     }
 }
 
 impl Packer {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.texture_size[0] == 0 || self.texture_size[1] == 0 || self.texture_size[0] > MAX_TEXTURE_SIZE || self.texture_size[1] > MAX_TEXTURE_SIZE {
+            return Err(format!("bad texture size. {:?}", self));
+        }
+
+        if self.spacing >= self.texture_size[0] || self.spacing >= self.texture_size[1] {
+            return Err(format!("spacing too large. {:?}", self));
+        }
+
+        Ok(())
+    }
+
+    // images larger than the texture are not packable, even when rotation is enabled
+    pub fn is_oversized(&self, image_size: [usize; 2]) -> bool {
+        image_size[0] > self.texture_size[0] || image_size[1] > self.texture_size[1]
+    }
+
+    pub(crate) fn texture_size_with_spacing(&self) -> [usize; 2] {
+        [self.texture_size[0] + self.spacing, self.texture_size[1] + self.spacing]
+    }
+
     pub fn pack(
         &self,
-        image_sizes: &Vec<[usize; 2]>
+        image_sizes: &[[usize; 2]]
     ) -> Result<Vec<Vec<Layout>>, String> {
+        Ok(self.pack_internal(image_sizes)?.into_iter().map(|a|a.layouts).collect())
+    }
+
+    pub fn pack_with_statistics(
+        &self,
+        image_sizes: &[[usize; 2]]
+    ) -> Result<Vec<PackedTexture>, String> {
+        let results = self.pack_internal(image_sizes)?;
+        Ok(results.into_iter().map(|packed| {
+            let free_spaces = self.free_spaces(&packed);
+            let statistics = self.statistics(&packed, &free_spaces, image_sizes);
+            PackedTexture { layouts: packed.layouts, statistics, free_spaces }
+        }).collect())
+    }
+
+    fn free_spaces(
+        &self,
+        packed: &Packed,
+    ) -> Vec<Rect> {
+        // free spaces extend over the spacing margin outside of texture
+        packed.spaces.rects()
+                .map(|a|Rect {
+                    size: [
+                        self.texture_size[0].saturating_sub(a.position[0]).min(a.size[0]),
+                        self.texture_size[1].saturating_sub(a.position[1]).min(a.size[1]),
+                    ],
+                    position: a.position,
+                })
+                .filter(|a|a.size[0] > 0 && a.size[1] > 0)
+                .collect()
+    }
+
+    fn statistics(
+        &self,
+        packed: &Packed,
+        free_spaces: &[Rect],
+        image_sizes: &[[usize; 2]]
+    ) -> TextureStatistics {
+        let texture_area = self.texture_size[0] * self.texture_size[1];
+        let used_area: usize = packed.layouts.iter().map(|a|image_sizes[a.index][0] * image_sizes[a.index][1]).sum();
+        let largest_free_rect = free_spaces.iter()
+                .map(|a|a.size)
+                .max_by_key(|a|a[0] * a[1])
+                .unwrap_or([0, 0]);
+
+        TextureStatistics {
+            sprite_count: packed.layouts.len(),
+            used_area,
+            wasted_area: texture_area - used_area,
+            occupancy: used_area as f64 * 100.0 / texture_area as f64,
+            largest_free_rect,
+        }
+    }
+
+    fn pack_internal(
+        &self,
+        image_sizes: &[[usize; 2]]
+    ) -> Result<Vec<Packed>, String> {
         let mut results = Vec::new();
         let mut images: Vec<Image> = image_sizes
-                    .into_iter()
+                    .iter()
                     .enumerate()
-                    .map(|(index, size)|Image{ index, size: size.clone() })
+                    .map(|(index, size)|Image{ index, size: *size })
                     .collect();
         images.sort_by(|a, b|(b.size[0] * b.size[1]).cmp(&(a.size[0] * a.size[1])));
 
-        if self.texture_size[0] == 0 || self.texture_size[1] == 0 || self.texture_size[0] > MAX_TEXTURE_SIZE || self.texture_size[1] > MAX_TEXTURE_SIZE {
-            return Err(format!("bad texture size. {:?}", self));
-        }
-
-        if self.spacing >= self.texture_size[0] || self.spacing >= self.texture_size[1] {
-            return Err(format!("spacing too large. {:?}", self));
-        }
+        self.validate()?;
 
-        let texture_size_with_spacing = [self.texture_size[0] + self.spacing, self.texture_size[1] + self.spacing];
+        let texture_size_with_spacing = self.texture_size_with_spacing();
         'image_loop: for image in images {
-            if image.size[0] > self.texture_size[0] || image.size[1] > self.texture_size[1] {
+            if self.is_oversized(image.size) {
                 return Err(format!("pack failed. image size larger than texture size. ({}, {}) > ({}, {})", image.size[0], image.size[1], self.texture_size[0], self.texture_size[1]));
             }
 
-            for mut packed in results.iter_mut() {
-                if self.try_pack_one(&mut packed, &image) {
+            for packed in results.iter_mut() {
+                if self.try_pack_one(packed, &image) {
                     continue 'image_loop;
                 }
             }
@@ -241,7 +516,7 @@ impl Packer {
             results.push(next);
         }
 
-        return Ok(results.into_iter().map(|a|a.layouts).collect());
+        Ok(results)
     }
 
     fn try_pack_one(
@@ -249,23 +524,32 @@ impl Packer {
         packed: &mut Packed,
         image: &Image,
     ) -> bool {
-        let min_size = self.spacing + 1;
-        let size_with_spacing = [image.size[0] + self.spacing, image.size[1] + self.spacing];
-        if let Some(space) = packed.spaces.find_space(size_with_spacing) {
-            let layout = Layout{ index: image.index, position: space.position, rotated: false };
-            packed.layouts.push(layout);
-            packed.spaces.exclude(&Rect{ position: space.position, size: size_with_spacing }, min_size);
+        if let Some((position, rotated)) = self.place(&mut packed.spaces, image.size, self.spacing + 1) {
+            packed.layouts.push(Layout{ index: image.index, position, rotated });
             return true;
         }
-        if self.enable_rotate && image.size[1] <= self.texture_size[0] && image.size[0] <= self.texture_size[1] {
+        false
+    }
+
+    // spaces narrower than min_size are dropped. they cannot hold any image when min_size is spacing + 1
+    pub(crate) fn place(
+        &self,
+        spaces: &mut Spaces,
+        size: [usize; 2],
+        min_size: usize,
+    ) -> Option<([usize; 2], bool)> {
+        let size_with_spacing = [size[0] + self.spacing, size[1] + self.spacing];
+        if let Some(space) = spaces.find_space(size_with_spacing) {
+            spaces.exclude(&Rect{ position: space.position, size: size_with_spacing }, min_size);
+            return Some((space.position, false));
+        }
+        if self.enable_rotate && size[1] <= self.texture_size[0] && size[0] <= self.texture_size[1] {
             let rotated_size = [size_with_spacing[1], size_with_spacing[0]];
-            if let Some(space) = packed.spaces.find_space(rotated_size) {
-                let layout = Layout{ index: image.index, position: space.position, rotated: true };
-                packed.layouts.push(layout);
-                packed.spaces.exclude(&Rect{ position: space.position, size: rotated_size }, min_size);
-                return true;
+            if let Some(space) = spaces.find_space(rotated_size) {
+                spaces.exclude(&Rect{ position: space.position, size: rotated_size }, min_size);
+                return Some((space.position, true));
             }
         }
-        return false;
+        None
     }
 }
@@ -0,0 +1,133 @@
+use crate::OutputData;
+use crate::format::escape_xml;
+use std::fmt::Write;
+use std::path::{Component, Path};
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; background: #202020; color: #e0e0e0; margin: 16px; }
+h2 { margin: 24px 0 8px 0; font-size: 18px; }
+.statistics { margin-bottom: 8px; font-size: 13px; color: #b0b0b0; }
+.texture { position: relative; display: inline-block; line-height: 0;
+  background-image: linear-gradient(45deg, #404040 25%, transparent 25%, transparent 75%, #404040 75%),
+    linear-gradient(45deg, #404040 25%, transparent 25%, transparent 75%, #404040 75%);
+  background-size: 16px 16px; background-position: 0 0, 8px 8px; background-color: #303030; }
+.sprite { position: absolute; box-sizing: border-box; border: 1px solid rgba(255, 255, 255, 0.25); }
+.sprite:hover { border: 2px solid #ffcc00; background: rgba(255, 204, 0, 0.2); }
+.sprite.rotated { border-style: dashed; }
+#info { position: fixed; right: 16px; top: 16px; min-width: 240px; padding: 8px 12px;
+  background: rgba(0, 0, 0, 0.85); border: 1px solid #606060; font-size: 13px; white-space: pre; }
+"#;
+
+const SCRIPT: &str = r#"
+var info = document.getElementById("info");
+document.querySelectorAll(".sprite").forEach(function (sprite) {
+  sprite.addEventListener("mouseenter", function () {
+    info.textContent = sprite.getAttribute("title");
+  });
+});
+"#;
+
+// Render a self-contained html page which shows textures with hoverable sprite regions.
+// texture_dir is the directory of the textures relative to the page, see relative_texture_dir.
+pub fn html_report(data: &OutputData, texture_dir: &str) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>image-packer report</title>\n");
+    writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE).unwrap();
+    html.push_str("<div id=\"info\">hover a sprite</div>\n");
+    writeln!(html, "<h1>{} textures, {} images</h1>", data.textures.len(), data.image_layouts.len()).unwrap();
+
+    for (texture_index, texture) in data.textures.iter().enumerate() {
+        writeln!(html, "<h2>{}</h2>", escape_xml(texture)).unwrap();
+        let layouts: Vec<_> = data.image_layouts.iter().filter(|a|a.texture == texture_index).collect();
+        if let Some(statistics) = data.statistics.as_ref().and_then(|a|a.get(texture_index)) {
+            writeln!(
+                html,
+                "<div class=\"statistics\">sprites {}, occupancy {:.2}%, used area {}, wasted area {}, largest free rect {}x{}</div>",
+                statistics.sprite_count,
+                statistics.occupancy,
+                statistics.used_area,
+                statistics.wasted_area,
+                statistics.largest_free_rect[0],
+                statistics.largest_free_rect[1]).unwrap();
+        } else if data.texture_size[0] > 0 && data.texture_size[1] > 0 {
+            // without packing statistics, occupancy is computed from sprite sizes
+            let used_area: usize = layouts.iter().map(|a|a.size[0] * a.size[1]).sum();
+            let texture_area = data.texture_size[0] * data.texture_size[1];
+            writeln!(
+                html,
+                "<div class=\"statistics\">sprites {}, occupancy {:.2}%, used area {}, wasted area {}</div>",
+                layouts.len(),
+                used_area as f64 * 100.0 / texture_area as f64,
+                used_area,
+                texture_area.saturating_sub(used_area)).unwrap();
+        } else {
+            writeln!(html, "<div class=\"statistics\">sprites {}</div>", layouts.len()).unwrap();
+        }
+
+        html.push_str("<div class=\"texture\">\n");
+        writeln!(html, "<img src=\"{}\">", escape_xml(&encode_url_path(&format!("{}{}", texture_dir, texture)))).unwrap();
+        for layout in layouts {
+            let size = if layout.rotated { [layout.size[1], layout.size[0]] } else { layout.size };
+            let title = format!(
+                "{}\nposition: {}, {}\nsize: {}x{}\nrotated: {}",
+                layout.name,
+                layout.position[0],
+                layout.position[1],
+                layout.size[0],
+                layout.size[1],
+                layout.rotated);
+            writeln!(
+                html,
+                "<div class=\"sprite{}\" style=\"left:{}px;top:{}px;width:{}px;height:{}px\" title=\"{}\"></div>",
+                if layout.rotated { " rotated" } else { "" },
+                layout.position[0],
+                layout.position[1],
+                size[0],
+                size[1],
+                escape_xml(&title)).unwrap();
+        }
+        html.push_str("</div>\n");
+    }
+
+    writeln!(html, "<script>{}</script>\n</body>\n</html>", SCRIPT).unwrap();
+    html
+}
+
+// Directory of textures as seen from the directory of the report, ending with '/' unless it is the same directory.
+// both directories must exist.
+pub fn relative_texture_dir(report_dir: &Path, texture_dir: &Path) -> Result<String, String> {
+    let canonicalize = |dir: &Path| {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        std::fs::canonicalize(dir).map_err(|e|format!("canonicalize failed. {}: {}", dir.display(), e))
+    };
+    let report_dir = canonicalize(report_dir)?;
+    let texture_dir = canonicalize(texture_dir)?;
+    let report_components: Vec<Component> = report_dir.components().collect();
+    let texture_components: Vec<Component> = texture_dir.components().collect();
+    let common = report_components.iter().zip(texture_components.iter()).take_while(|(a, b)|a == b).count();
+    // on another drive there is no relative path
+    if common == 0 {
+        return Ok(format!("{}/", texture_dir.display()));
+    }
+
+    let mut relative = "../".repeat(report_components.len() - common);
+    for component in texture_components[common..].iter() {
+        let name = component.as_os_str().to_str().ok_or_else(||format!("OsStr::to_str failed. {}", texture_dir.display()))?;
+        relative.push_str(name);
+        relative.push('/');
+    }
+    Ok(relative)
+}
+
+// percent-encode a relative url path. '/' separates segments and is kept
+fn encode_url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            write!(encoded, "%{:02X}", byte).unwrap();
+        }
+    }
+    encoded
+}
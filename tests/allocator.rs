@@ -0,0 +1,107 @@
+use image_packer::*;
+use proptest::prelude::*;
+use proptest::array::uniform2;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Allocate([usize; 2]),
+    Deallocate(usize),
+    DeallocateMany(usize),
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => uniform2(1usize..256).prop_map(Op::Allocate),
+        1 => any::<usize>().prop_map(Op::Deallocate),
+        1 => any::<usize>().prop_map(Op::DeallocateMany),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn test_allocate_deallocate(spacing in 0usize..2, enable_rotate in any::<bool>(), ref ops in proptest::collection::vec(op_strategy(), 1..200)) {
+        let texture_size = [512, 512];
+        let mut allocator = AtlasAllocator::new(Packer { texture_size, spacing, enable_rotate }).unwrap();
+        let mut live: Vec<(AllocationId, [usize; 2])> = Vec::new();
+
+        for op in ops.iter() {
+            match *op {
+                Op::Allocate(size) => {
+                    if let Some(allocation) = allocator.allocate(size) {
+                        prop_assert!(live.iter().all(|(id, _)|*id != allocation.id));
+                        prop_assert_eq!(Some(allocation), allocator.get(allocation.id));
+                        live.push((allocation.id, size));
+                    }
+                },
+                Op::Deallocate(n) => {
+                    if live.is_empty() {
+                        prop_assert!(allocator.ids().next().is_none());
+                    } else {
+                        let (id, _) = live.remove(n % live.len());
+                        prop_assert!(allocator.deallocate(id));
+                        prop_assert!(!allocator.deallocate(id));
+                        prop_assert!(allocator.get(id).is_none());
+                    }
+                },
+                Op::DeallocateMany(n) => {
+                    // every other live allocation from n, with an already freed id mixed in
+                    let ids: Vec<AllocationId> = live.iter().skip(n % 2).step_by(2).map(|a|a.0).collect();
+                    live.retain(|a|!ids.contains(&a.0));
+                    let stale = ids.first().copied();
+                    prop_assert_eq!(ids.len(), allocator.deallocate_many(ids.into_iter().chain(stale)));
+                },
+            }
+
+            // assert occupancy queries
+            prop_assert_eq!(live.len(), allocator.len());
+            let used_area: usize = live.iter().map(|(_, s)|s[0] * s[1]).sum();
+            prop_assert_eq!(used_area, allocator.used_area());
+            prop_assert!(allocator.occupancy() <= 100.0);
+
+            // assert all allocations are inside of texture
+            let texture = Rect { position: [0, 0], size: texture_size };
+            let rects: Vec<Rect> = live.iter().map(|(id, _)|allocator.allocated_rect(*id).unwrap()).collect();
+            for rect in rects.iter() {
+                prop_assert!(texture.include(rect), "{:?}", rect);
+            }
+
+            // assert allocations do not have intersection
+            for (i, rect1) in rects.iter().enumerate() {
+                for rect2 in rects.iter().skip(i + 1) {
+                    let rect1 = Rect { position: rect1.position, size: [rect1.size[0] + spacing, rect1.size[1] + spacing] };
+                    let rect2 = Rect { position: rect2.position, size: [rect2.size[0] + spacing, rect2.size[1] + spacing] };
+                    prop_assert!(!rect1.has_intersection(&rect2), "{:?} {:?}", rect1, rect2);
+                }
+            }
+        }
+
+        // released spaces are merged back into the whole texture
+        let ids: Vec<AllocationId> = allocator.ids().collect();
+        prop_assert_eq!(ids.len(), allocator.deallocate_many(ids));
+        prop_assert!(allocator.allocate(texture_size).is_some());
+    }
+}
+
+#[test]
+fn test_reuse_after_deallocate() {
+    let mut allocator = AtlasAllocator::new(Packer { texture_size: [64, 64], spacing: 0, enable_rotate: false }).unwrap();
+    let first = allocator.allocate([64, 64]).unwrap();
+    assert!(allocator.allocate([1, 1]).is_none());
+    assert!(allocator.deallocate(first.id));
+    let second = allocator.allocate([64, 64]).unwrap();
+    assert_ne!(first.id, second.id);
+    assert!(!allocator.deallocate(first.id));
+    assert_eq!(100.0, allocator.occupancy());
+}
+
+#[test]
+fn test_merge_released_neighbours() {
+    // four quarters freed one by one make the whole texture again
+    let mut allocator = AtlasAllocator::new(Packer { texture_size: [64, 64], spacing: 1, enable_rotate: false }).unwrap();
+    let quarters: Vec<Allocation> = (0..4).map(|_|allocator.allocate([31, 31]).unwrap()).collect();
+    assert!(allocator.allocate([1, 1]).is_none());
+    for (i, quarter) in quarters.iter().enumerate() {
+        assert!(allocator.deallocate(quarter.id));
+        assert_eq!(i == 3, allocator.allocate([63, 63]).map(|a|allocator.deallocate(a.id)).is_some());
+    }
+}
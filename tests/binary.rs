@@ -0,0 +1,77 @@
+use image_packer::*;
+use image_packer::format::binary;
+use proptest::prelude::*;
+use proptest::array::uniform2;
+
+fn output_data_strategy() -> impl Strategy<Value = OutputData> {
+    (1usize..4, uniform2(1usize..4097), any::<bool>(), any::<bool>()).prop_flat_map(|(texture_count, texture_size, counter_clockwise, has_statistics)| {
+        let image_layout = ("[a-z0-9_/.@ ]{0,24}", 0..texture_count, uniform2(0usize..4096), uniform2(1usize..4096), any::<bool>())
+            .prop_map(|(name, texture, position, size, rotated)|ImageLayoutInfo { name, texture, position, size, rotated });
+        let statistics = (0usize..1000, any::<u32>(), any::<u32>(), 0.0f64..100.0, uniform2(0usize..4096))
+            .prop_map(|(sprite_count, used_area, wasted_area, occupancy, largest_free_rect)| {
+                TextureStatistics { sprite_count, used_area: used_area as usize, wasted_area: wasted_area as usize, occupancy, largest_free_rect }
+            });
+        (
+            proptest::collection::vec(image_layout, 0..50),
+            proptest::collection::vec("[a-z0-9-]{1,16}\\.png", texture_count),
+            proptest::collection::vec(statistics, texture_count),
+        ).prop_map(move |(image_layouts, textures, statistics)| {
+            let rotation = if counter_clockwise { Rotation::CounterClockwise } else { Rotation::Clockwise };
+            OutputData { image_layouts, textures, texture_size, rotation, statistics: if has_statistics { Some(statistics) } else { None } }
+        })
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_round_trip(ref data in output_data_strategy()) {
+        let bytes = binary::write(data).unwrap();
+        let read = binary::read(&bytes).unwrap();
+        prop_assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(data).unwrap());
+    }
+}
+
+#[test]
+fn test_binary_layout() {
+    let data = OutputData {
+        image_layouts: vec![ImageLayoutInfo { name: String::from("a.png"), texture: 0, position: [1, 2], size: [3, 4], rotated: true }],
+        textures: vec![String::from("t.png")],
+        texture_size: [256, 128],
+        rotation: Rotation::Clockwise,
+        statistics: None,
+    };
+    let bytes = binary::write(&data).unwrap();
+    assert_eq!(bytes.len(), binary::HEADER_SIZE + binary::TEXTURE_RECORD_SIZE + binary::SPRITE_RECORD_SIZE + 10);
+    assert_eq!(&bytes[0..8], b"IPAK\x01\x00\x00\x00");
+    assert_eq!(&bytes[8..12], &256u32.to_le_bytes());
+    assert_eq!(&bytes[40..48], &[5, 0, 0, 0, 5, 0, 0, 0]);
+    assert_eq!(&bytes[bytes.len() - 10..], b"t.pnga.png");
+
+    // json written by the cli reads into the same data
+    let json = serde_json::to_vec(&data).unwrap();
+    let from_json: OutputData = serde_json::from_slice(&json).unwrap();
+    assert_eq!(binary::write(&from_json).unwrap(), bytes);
+
+    assert!(binary::read(&bytes[..bytes.len() - 1]).is_err());
+    assert!(binary::read(b"JSON").is_err());
+    let mut bad_version = bytes.clone();
+    bad_version[4] = 2;
+    assert!(binary::read(&bad_version).is_err());
+    let mut bad_texture = bytes.clone();
+    bad_texture[binary::HEADER_SIZE + binary::TEXTURE_RECORD_SIZE + 8] = 1;
+    assert!(binary::read(&bad_texture).is_err());
+
+    // huge counts fail without overflowing or allocating for them
+    let mut huge_counts = bytes.clone();
+    huge_counts[16..28].copy_from_slice(&[0xff; 12]);
+    huge_counts[6] = 1;
+    let error = binary::read(&huge_counts).unwrap_err();
+    let expected = if cfg!(target_pointer_width = "64") { "differs from the size in its header" } else { "header counts overflow" };
+    assert!(error.contains(expected), "{}", error);
+
+    // counter clockwise rotation is a header flag
+    let data = OutputData { rotation: Rotation::CounterClockwise, ..data };
+    let bytes = binary::write(&data).unwrap();
+    assert_eq!(&bytes[6..8], &[2, 0]);
+    assert_eq!(binary::read(&bytes).unwrap().rotation, Rotation::CounterClockwise);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use image_packer::cache::*;
+use common::temp_dir;
+
+#[test]
+fn test_manifest() {
+    assert_eq!(content_hash(b""), 0xcbf29ce484222325);
+    assert_eq!(content_hash(b"a"), 0xaf63dc4c8601ec8c);
+
+    let dir = temp_dir("manifest");
+    std::fs::write(dir.join("texture000.png"), b"").unwrap();
+    let manifest = BuildManifest {
+        version: String::from("0.1.0"),
+        options: serde_json::json!({ "spacing": 1 }),
+        inputs: vec![ManifestInput { name: String::from("a.png"), path: String::from("in/a.png"), hash: String::from("0123456789abcdef") }],
+        outputs: vec![String::from("texture000.png")],
+    };
+    assert_eq!(manifest_filename("texture"), ".image-packer-cache-texture.json");
+    assert_eq!(manifest_filename("ui/texture"), ".image-packer-cache-ui_texture.json");
+    let manifest_path = dir.join(manifest_filename("texture"));
+    assert!(BuildManifest::load(&manifest_path).is_none());
+    manifest.save(&manifest_path).unwrap();
+    let previous = BuildManifest::load(&manifest_path).unwrap();
+    assert_eq!(previous, manifest);
+    assert!(manifest.is_up_to_date(&previous, &dir));
+
+    let mut changed = manifest.clone();
+    changed.inputs[0].hash = String::from("fedcba9876543210");
+    assert!(!changed.is_up_to_date(&previous, &dir));
+    let mut changed = manifest.clone();
+    changed.options = serde_json::json!({ "spacing": 2 });
+    assert!(!changed.is_up_to_date(&previous, &dir));
+
+    std::fs::remove_file(dir.join("texture000.png")).unwrap();
+    assert!(!manifest.is_up_to_date(&previous, &dir));
+
+    BuildManifest::remove(&manifest_path).unwrap();
+    assert!(BuildManifest::load(&manifest_path).is_none());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_decode_cache() {
+    let dir = temp_dir("decode-cache");
+    let shared = dir.join("shared.png");
+    let single = dir.join("single.png");
+    let write = |path: &std::path::Path, value: u8|image::RgbaImage::from_pixel(2, 2, image::Rgba([value, 0, 0, 255])).save(path).unwrap();
+    write(&shared, 1);
+    write(&single, 1);
+
+    // a shared image is decoded once. the file is changed to find out whether it is decoded again
+    let cache = DecodeCache::new(vec![shared.as_path(), single.as_path(), shared.as_path()].into_iter());
+    let first = cache.decode(&shared).unwrap();
+    write(&shared, 2);
+    let second = cache.decode(&shared).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert_eq!(second.get_pixel(0, 0)[0], 1);
+    // and dropped after its last use
+    assert_eq!(cache.decode(&shared).unwrap().get_pixel(0, 0)[0], 2);
+
+    // images used once are not kept
+    let first = cache.decode(&single).unwrap();
+    assert!(!std::sync::Arc::ptr_eq(&first, &cache.decode(&single).unwrap()));
+
+    // a released use is not waited for
+    let cache = DecodeCache::new(vec![shared.as_path(), shared.as_path()].into_iter());
+    cache.release(&shared);
+    cache.decode(&shared).unwrap();
+    write(&shared, 3);
+    assert_eq!(cache.decode(&shared).unwrap().get_pixel(0, 0)[0], 3);
+
+    assert!(cache.decode(&dir.join("missing.png")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
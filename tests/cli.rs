@@ -0,0 +1,406 @@
+mod common;
+
+use image::{Rgba, RgbaImage};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::io::BufRead;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+use common::temp_dir;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_image-packer")).args(args).output().unwrap()
+}
+
+// image with distinct pixels, so that a sprite rotated or placed wrongly changes the texture
+fn write_png(path: &Path, size: [u32; 2], seed: u8) {
+    let image = RgbaImage::from_fn(size[0], size[1], |x, y|Rgba([x as u8, y as u8, seed, 255]));
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image.save(path).unwrap();
+}
+
+fn write_pngs(dir: &Path, sizes: &[[u32; 2]]) {
+    for (i, size) in sizes.iter().enumerate() {
+        write_png(&dir.join(format!("sprite{:02}.png", i)), *size, i as u8);
+    }
+}
+
+// contents of the output files. the build manifest records the output path, so it is left out
+fn read_outputs(dir: &Path) -> BTreeMap<String, Vec<u8>> {
+    std::fs::read_dir(dir).unwrap()
+        .map(|a|a.unwrap().path())
+        .filter(|a|!a.file_name().unwrap().to_str().unwrap().starts_with('.'))
+        .map(|a|(a.file_name().unwrap().to_str().unwrap().to_string(), std::fs::read(&a).unwrap()))
+        .collect()
+}
+
+// move modification times of the files back, so that a rewrite is noticed whatever the time resolution is
+fn set_old_mtimes(dir: &Path) -> BTreeMap<String, SystemTime> {
+    let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    for entry in std::fs::read_dir(dir).unwrap() {
+        std::fs::File::options().write(true).open(entry.unwrap().path()).unwrap().set_modified(old).unwrap();
+    }
+    mtimes(dir)
+}
+
+fn mtimes(dir: &Path) -> BTreeMap<String, SystemTime> {
+    std::fs::read_dir(dir).unwrap()
+        .map(|a|a.unwrap())
+        .map(|a|(a.file_name().to_str().unwrap().to_string(), a.metadata().unwrap().modified().unwrap()))
+        .collect()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn test_jobs_output_identical() {
+    let dir = temp_dir("cli-jobs");
+    write_pngs(&dir.join("in"), &[[40, 20], [20, 40], [30, 30], [50, 12], [12, 50], [24, 36], [36, 24], [60, 10], [10, 60], [32, 32], [44, 18], [18, 44], [56, 28], [28, 56], [40, 40], [48, 30]]);
+    let input = dir.join("in").display().to_string();
+    let sequential = dir.join("sequential").display().to_string();
+    let parallel = dir.join("parallel").display().to_string();
+    let options = ["-s", "64,64", "--spacing", "1", "--enable-rotate", "--debug-overlay", "--output-statistics"];
+
+    let output = run(&[&options[..], &["--jobs", "1", &input, &sequential]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = run(&[&options[..], &["--jobs", "4", "--textures-in-flight", "3", &input, &parallel]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let sequential = read_outputs(Path::new(&sequential));
+    let parallel = read_outputs(Path::new(&parallel));
+    // more textures than --textures-in-flight, so that they are composed in several chunks
+    assert!(sequential.contains_key("texture004.png"), "{:?}", sequential.keys());
+    let data: serde_json::Value = serde_json::from_slice(&sequential["texture-information.json"]).unwrap();
+    assert!(data["image_layouts"].as_array().unwrap().iter().any(|a|a["rotated"] == true));
+    assert_eq!(sequential.keys().collect::<Vec<_>>(), parallel.keys().collect::<Vec<_>>());
+    for (name, content) in sequential.iter() {
+        assert!(parallel[name] == *content, "{} differs", name);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_rotated_sprites_composed_rotated() {
+    let dir = temp_dir("cli-rotated");
+    let sizes = [[40, 8], [8, 40], [30, 12], [12, 30], [20, 20]];
+    write_pngs(&dir.join("in"), &sizes);
+    let input = dir.join("in").display().to_string();
+
+    // sprites are rotated clockwise by default, and counter clockwise for libgdx
+    for (data_format, name) in [("information", "clockwise"), ("libgdx,information", "counter-clockwise")] {
+        let output_path = dir.join(name);
+        let output = run(&["-s", "48,48", "--enable-rotate", "--data-format", data_format, &input, output_path.to_str().unwrap()]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let data: serde_json::Value = serde_json::from_slice(&std::fs::read(output_path.join("texture-information.json")).unwrap()).unwrap();
+        let layouts = data["image_layouts"].as_array().unwrap();
+        assert!(layouts.iter().any(|a|a["rotated"] == true));
+
+        // each layout rect holds the sprite turned as the data says
+        for layout in layouts {
+            let sprite = image::open(dir.join("in").join(layout["name"].as_str().unwrap())).unwrap().to_rgba8();
+            let expected = match (layout["rotated"].as_bool().unwrap(), name) {
+                (false, _) => sprite,
+                (true, "clockwise") => image::imageops::rotate90(&sprite),
+                (true, _) => image::imageops::rotate270(&sprite),
+            };
+            let texture = image::open(output_path.join(data["textures"][layout["texture"].as_u64().unwrap() as usize].as_str().unwrap())).unwrap().to_rgba8();
+            let [x, y] = [layout["position"][0].as_u64().unwrap() as u32, layout["position"][1].as_u64().unwrap() as u32];
+            let placed = image::imageops::crop_imm(&texture, x, y, expected.width(), expected.height()).to_image();
+            assert!(placed == expected, "{} in {}", layout["name"], name);
+        }
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_decode_error_policies() {
+    let dir = temp_dir("cli-decode-error");
+    write_pngs(&dir.join("in"), &[[16, 16], [24, 24]]);
+    std::fs::write(dir.join("in/.DS_Store"), b"\0\0\0\x01Bud1").unwrap();
+    std::fs::write(dir.join("in/broken.png"), b"not a png").unwrap();
+    let input = dir.join("in").display().to_string();
+    let output_path = dir.join("out").display().to_string();
+
+    let output = run(&["-s", "64,64", "--any-extension", &input, &output_path]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("decode failed"), "{}", stderr(&output));
+
+    // without --any-extension .DS_Store is skipped by its extension
+    let output = run(&["-s", "64,64", "--on-decode-error", "warn", &input, &output_path]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stderr_text = stderr(&output);
+    assert!(stderr_text.contains("warning: decode failed. "), "{}", stderr_text);
+    assert!(stderr_text.contains("skipped 2 files"), "{}", stderr_text);
+    assert!(stderr_text.contains(".DS_Store (not an image extension)"), "{}", stderr_text);
+    assert!(stderr_text.contains("broken.png (decode failed)"), "{}", stderr_text);
+
+    let output = run(&["-s", "64,64", "--on-decode-error", "skip", "--any-extension", "--force", &input, &output_path]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stderr_text = stderr(&output);
+    assert!(!stderr_text.contains("warning:"), "{}", stderr_text);
+    assert!(stderr_text.contains("skipped 2 files"), "{}", stderr_text);
+    assert!(stderr_text.contains(".DS_Store (decode failed)"), "{}", stderr_text);
+    let data: serde_json::Value = serde_json::from_slice(&std::fs::read(dir.join("out/texture-information.json")).unwrap()).unwrap();
+    assert_eq!(data["image_layouts"].as_array().unwrap().len(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_decode_error_after_probe() {
+    let dir = temp_dir("cli-decode-error-after-probe");
+    write_pngs(&dir.join("in"), &[[16, 16], [24, 24], [32, 32]]);
+    // the header is intact, so the size is probed, but the pixels cannot be decoded
+    let content = std::fs::read(dir.join("in/sprite02.png")).unwrap();
+    std::fs::write(dir.join("in/sprite02.png"), &content[..content.len() / 2]).unwrap();
+    let input = dir.join("in").display().to_string();
+    let output_path = dir.join("out").display().to_string();
+
+    let output = run(&["-s", "64,64", "--on-decode-error", "skip", "--output-statistics", &input, &output_path]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("texture000.png: sprites 2, occupancy 20.31%, used area 832, wasted area 3264,"), "{}", stdout(&output));
+    assert!(stderr(&output).contains("sprite02.png (decode failed)"), "{}", stderr(&output));
+
+    // statistics agree with the layouts written
+    let data: serde_json::Value = serde_json::from_slice(&std::fs::read(dir.join("out/texture-information.json")).unwrap()).unwrap();
+    assert_eq!(data["image_layouts"].as_array().unwrap().len(), 2);
+    assert_eq!(data["statistics"][0]["sprite_count"], 2);
+    assert_eq!(data["statistics"][0]["used_area"], 16 * 16 + 24 * 24);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_atlases_in_one_output_directory() {
+    let dir = temp_dir("cli-shared-output");
+    write_pngs(&dir.join("in/a"), &[[16, 16], [24, 24]]);
+    write_pngs(&dir.join("in/b"), &[[32, 16]]);
+    let project_path = dir.join("atlas.toml");
+    std::fs::write(&project_path, r#"
+texture_size = [64, 64]
+output_path = "shared"
+
+[[atlas]]
+name = "a"
+input_paths = ["in/a"]
+texture_prefix = "a"
+output_data_filename = "a.json"
+
+[[atlas]]
+name = "b"
+input_paths = ["in/b"]
+texture_prefix = "b"
+output_data_filename = "b.json"
+"#).unwrap();
+    let project = project_path.display().to_string();
+
+    let output = run(&["build", &project]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let outputs = read_outputs(&dir.join("shared"));
+    assert_eq!(outputs.keys().collect::<Vec<_>>(), ["a.json", "a000.png", "b.json", "b000.png"]);
+    assert!(dir.join("shared/.image-packer-cache-a.json").is_file());
+    assert!(dir.join("shared/.image-packer-cache-b.json").is_file());
+    let output = run(&["build", &project]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output).matches("up to date").count(), 2, "{}", stdout(&output));
+
+    // files written by both atlases are errors
+    let output = run(&["build", "--output-data-filename", "data.json", &project]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("data.json is written by a and b"), "{}", stderr(&output));
+    let output = run(&["build", "--texture-prefix", "texture", &project]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("atlases a and b have the same texture prefix texture"), "{}", stderr(&output));
+
+    // split atlases share the output directory
+    write_pngs(&dir.join("split/ui"), &[[16, 16]]);
+    write_pngs(&dir.join("split/fonts"), &[[16, 16]]);
+    let split = dir.join("split").display().to_string();
+    let split_output = dir.join("split-out").display().to_string();
+    let output = run(&["-s", "64,64", "--split-subdirectories", &split, &split_output]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let outputs = read_outputs(&dir.join("split-out"));
+    assert_eq!(outputs.keys().collect::<Vec<_>>(), ["fonts.json", "fonts000.png", "ui.json", "ui000.png"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_skip_unchanged_build() {
+    let dir = temp_dir("cli-skip-unchanged");
+    write_pngs(&dir.join("in"), &[[16, 16], [24, 24]]);
+    let input = dir.join("in").display().to_string();
+    let output_path = dir.join("out").display().to_string();
+    let args = ["-s", "64,64", &input, &output_path];
+
+    let output = run(&args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout(&output).contains("up to date"));
+    let built = set_old_mtimes(&dir.join("out"));
+    assert!(built.contains_key(".image-packer-cache-texture.json"));
+
+    // nothing is written when inputs and options are unchanged
+    let output = run(&args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("up to date"), "{}", stdout(&output));
+    assert_eq!(mtimes(&dir.join("out")), built);
+
+    // --force builds anyway
+    let output = run(&[&["--force"], &args[..]].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout(&output).contains("up to date"));
+    assert!(stdout(&output).contains("texture000.png: sprites 2"), "{}", stdout(&output));
+    assert_ne!(mtimes(&dir.join("out"))[".image-packer-cache-texture.json"], built[".image-packer-cache-texture.json"]);
+
+    // so do changed inputs and options
+    write_png(&dir.join("in/sprite01.png"), [24, 24], 100);
+    let output = run(&args);
+    assert!(!stdout(&output).contains("up to date"));
+    let output = run(&args);
+    assert!(stdout(&output).contains("up to date"));
+    let output = run(&["-s", "64,64", "--spacing", "1", &input, &output_path]);
+    assert!(!stdout(&output).contains("up to date"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// stops a watching process also when the test fails
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn test_watch_rewrites_changed_outputs() {
+    let dir = temp_dir("cli-watch");
+    // a texture for each sprite
+    write_pngs(&dir.join("in"), &[[64, 64], [64, 64]]);
+    let input = dir.join("in").display().to_string();
+    let output_path = dir.join("out").display().to_string();
+
+    let mut child = KillOnDrop(Command::new(env!("CARGO_BIN_EXE_image-packer"))
+        .args(["-s", "64,64", "--watch", &input, &output_path])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap());
+    let (sender, receiver) = mpsc::channel();
+    let stdout = child.0.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    let wait_line = |expected: &str| {
+        loop {
+            let line = receiver.recv_timeout(Duration::from_secs(30)).unwrap();
+            if line.contains(expected) {
+                break;
+            }
+        }
+    };
+    wait_line("watching for changes");
+    let built = set_old_mtimes(&dir.join("out"));
+
+    // the rebuild finishes when the manifest is written again
+    write_png(&dir.join("in/sprite01.png"), [64, 64], 100);
+    wait_line("texture001.png: sprites 1");
+    let manifest_path = dir.join("out/.image-packer-cache-texture.json");
+    let started = Instant::now();
+    while std::fs::metadata(&manifest_path).and_then(|a|a.modified()).map_or(true, |a|a == built[".image-packer-cache-texture.json"]) {
+        assert!(started.elapsed() < Duration::from_secs(30));
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    drop(child);
+
+    // outputs with the same contents are left untouched
+    let rebuilt = mtimes(&dir.join("out"));
+    assert_eq!(rebuilt["texture000.png"], built["texture000.png"]);
+    assert_eq!(rebuilt["texture-information.json"], built["texture-information.json"]);
+    assert_ne!(rebuilt["texture001.png"], built["texture001.png"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_dry_run() {
+    let dir = temp_dir("cli-dry-run");
+    write_pngs(&dir.join("in"), &[[16, 16], [24, 24]]);
+    let input = dir.join("in").display().to_string();
+    let output_path = dir.join("out").display().to_string();
+
+    let output = run(&["-s", "64,64", "--dry-run", &input, &output_path]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("texture000.png: sprites 2"), "{}", stdout(&output));
+    assert!(stdout(&output).contains("1 textures, 2 images, occupancy 20.31%"), "{}", stdout(&output));
+    assert!(!dir.join("out").exists());
+
+    // images larger than the texture are listed and fail the run
+    write_png(&dir.join("in/large.png"), [80, 16], 0);
+    let output = run(&["-s", "64,64", "--dry-run", &input, &output_path]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("1 images are larger than the texture size 64x64"), "{}", stderr(&output));
+    assert!(stderr(&output).contains("large.png (80x16)"), "{}", stderr(&output));
+    assert!(stdout(&output).contains("1 textures, 2 images"), "{}", stdout(&output));
+    assert!(!dir.join("out").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_sprite_shared_by_atlases() {
+    let dir = temp_dir("cli-shared-sprite");
+    write_pngs(&dir.join("in/a"), &[[16, 16]]);
+    write_pngs(&dir.join("in/b"), &[[24, 24]]);
+    write_png(&dir.join("in/shared.png"), [32, 32], 7);
+    let project_path = dir.join("atlas.toml");
+    let project = |spacing: usize| format!(r#"
+texture_size = [64, 64]
+
+[[atlas]]
+name = "a"
+input_paths = ["in/a", "in/shared.png"]
+output_path = "out/a"
+spacing = {}
+
+[[atlas]]
+name = "b"
+input_paths = ["in/b", "in/shared.png"]
+output_path = "out/b"
+"#, spacing);
+    std::fs::write(&project_path, project(0)).unwrap();
+    let args = ["build", "--jobs", "2", project_path.to_str().unwrap()];
+
+    let output = run(&args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let a = read_outputs(&dir.join("out/a"));
+    let b = read_outputs(&dir.join("out/b"));
+    for data in [&a["texture-information.json"], &b["texture-information.json"]] {
+        let data: serde_json::Value = serde_json::from_slice(data).unwrap();
+        assert!(data["image_layouts"].as_array().unwrap().iter().any(|a|a["name"] == "shared.png"));
+    }
+
+    // the use of the skipped atlas is released, and the other still gets the image
+    std::fs::write(&project_path, project(1)).unwrap();
+    let output = run(&args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("out/b: up to date"), "{}", stdout(&output));
+    assert!(read_outputs(&dir.join("out/a"))["texture000.png"] != a["texture000.png"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
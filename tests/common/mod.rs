@@ -0,0 +1,28 @@
+use image_packer::*;
+use std::path::PathBuf;
+
+// empty directory for a test. name must be unique among tests
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("image-packer-test-{}-{}", name, std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// three sprites on two textures. the second sprite is rotated
+#[allow(dead_code)]
+pub fn output_data() -> OutputData {
+    OutputData {
+        image_layouts: vec![
+            ImageLayoutInfo { name: String::from("hero/walk_01.png"), texture: 0, position: [0, 0], size: [32, 48], rotated: false },
+            ImageLayoutInfo { name: String::from("hero/walk_02.png"), texture: 0, position: [32, 0], size: [32, 48], rotated: true },
+            ImageLayoutInfo { name: String::from("tree.png"), texture: 1, position: [0, 0], size: [64, 64], rotated: false },
+        ],
+        textures: vec![String::from("texture000.png"), String::from("texture001.png")],
+        texture_size: [128, 128],
+        rotation: Rotation::Clockwise,
+        statistics: None,
+    }
+}
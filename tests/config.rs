@@ -0,0 +1,122 @@
+mod common;
+
+use image_packer::config::*;
+use common::temp_dir;
+
+#[test]
+fn test_load_project() {
+    let dir = temp_dir("load-project");
+    let project_path = dir.join("atlas.toml");
+    std::fs::write(&project_path, r#"
+jobs = 2
+textures_in_flight = 3
+spacing = 1
+texture_size = [512, 512]
+
+[[atlas]]
+name = "ui"
+input_paths = ["ui", "@lists/ui.txt"]
+output_path = "out/ui"
+spacing = 2
+
+[[atlas]]
+name = "fonts"
+input_paths = ["fonts/*.png"]
+output_path = "out/fonts"
+"#).unwrap();
+
+    let project = ProjectConfig::load(&project_path).unwrap();
+    assert_eq!(project.jobs, Some(2));
+    assert_eq!(project.textures_in_flight, Some(3));
+    let atlases = project.atlas_configs();
+    assert_eq!(atlases.len(), 2);
+
+    assert_eq!(atlases[0].name.as_deref(), Some("ui"));
+    assert_eq!(atlases[0].spacing, Some(2));
+    assert_eq!(atlases[0].texture_size, Some([512, 512]));
+    assert_eq!(atlases[0].input_paths, Some(vec![
+        dir.join("ui").display().to_string(),
+        format!("@{}", dir.join("lists/ui.txt").display()),
+    ]));
+    assert_eq!(atlases[0].output_path, Some(dir.join("out/ui").display().to_string()));
+
+    assert_eq!(atlases[1].spacing, Some(1));
+    assert_eq!(atlases[1].input_paths, Some(vec![dir.join("fonts/*.png").display().to_string()]));
+
+    // command line values override the file
+    let overrides = AtlasConfig {
+        spacing: Some(4),
+        enable_rotate: Some(true),
+        ..Default::default()
+    };
+    let merged = atlases[0].clone().merge(overrides);
+    assert_eq!(merged.spacing, Some(4));
+    assert_eq!(merged.enable_rotate, Some(true));
+    assert_eq!(merged.texture_size, Some([512, 512]));
+
+    let json_path = dir.join("atlas.json");
+    std::fs::write(&json_path, r#"{"input_paths": ["images"], "output_path": "out", "enable_rotate": true}"#).unwrap();
+    let atlases = ProjectConfig::load(&json_path).unwrap().atlas_configs();
+    assert_eq!(atlases.len(), 1);
+    assert_eq!(atlases[0].enable_rotate, Some(true));
+    assert_eq!(atlases[0].output_path, Some(dir.join("out").display().to_string()));
+
+    // misspelled keys are errors, at the top level and in atlases
+    std::fs::write(&project_path, "texure_size = [512, 512]\n").unwrap();
+    let error = ProjectConfig::load(&project_path).unwrap_err();
+    assert!(error.contains("texure_size"), "{}", error);
+    std::fs::write(&project_path, "[[atlas]]\nname = \"ui\"\n\n[[atlas]]\nspaceing = 2\n").unwrap();
+    let error = ProjectConfig::load(&project_path).unwrap_err();
+    assert!(error.contains("atlas[1].spaceing"), "{}", error);
+    std::fs::write(&json_path, r#"{"output_path": "out", "enable_rotation": true}"#).unwrap();
+    assert!(ProjectConfig::load(&json_path).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_split_subdirectories() {
+    let dir = temp_dir("split-subdirectories");
+    for path in ["first/ui", "first/fonts", "second/ui"] {
+        std::fs::create_dir_all(dir.join(path)).unwrap();
+    }
+    std::fs::write(dir.join("first/loose.png"), b"").unwrap();
+
+    let config = AtlasConfig {
+        input_paths: Some(vec![dir.join("first").display().to_string(), dir.join("second").display().to_string()]),
+        output_path: Some(String::from("out")),
+        spacing: Some(2),
+        split_subdirectories: Some(true),
+        ..Default::default()
+    };
+    let atlases = config.split_subdirectories().unwrap();
+    assert_eq!(atlases.len(), 2);
+    assert_eq!(atlases[0].name.as_deref(), Some("fonts"));
+    assert_eq!(atlases[0].input_paths, Some(vec![dir.join("first/fonts").display().to_string()]));
+    assert_eq!(atlases[0].output_path, Some(String::from("out")));
+    assert_eq!(atlases[0].texture_prefix.as_deref(), Some("fonts"));
+    assert_eq!(atlases[0].output_data_filename.as_deref(), Some("fonts.json"));
+    assert_eq!(atlases[0].html_report_filename, None);
+    assert_eq!(atlases[0].spacing, Some(2));
+    assert_eq!(atlases[1].name.as_deref(), Some("ui"));
+    assert_eq!(atlases[1].input_paths, Some(vec![
+        dir.join("first/ui").display().to_string(),
+        dir.join("second/ui").display().to_string(),
+    ]));
+    assert_eq!(atlases[1].output_path, Some(String::from("out")));
+    assert_eq!(atlases[1].texture_prefix.as_deref(), Some("ui"));
+
+    // names set are prefixed with the subdirectory
+    let config = AtlasConfig {
+        texture_prefix: Some(String::from("texture")),
+        output_data_filename: Some(String::from("data/info.json")),
+        html_report: Some(true),
+        ..config
+    };
+    let atlases = config.split_subdirectories().unwrap();
+    assert_eq!(atlases[1].texture_prefix.as_deref(), Some("ui-texture"));
+    assert_eq!(atlases[1].output_data_filename.as_deref(), Some("data/ui-info.json"));
+    assert_eq!(atlases[1].html_report_filename.as_deref(), Some("ui.html"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
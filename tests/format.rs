@@ -0,0 +1,384 @@
+use image_packer::*;
+use image_packer::format::*;
+
+mod common;
+use common::{output_data, temp_dir};
+
+#[test]
+fn test_texture_packer() {
+    let data = output_data();
+    let files = export(DataFormat::TexturePackerHash, &data, "texture-information.json").unwrap();
+    assert_eq!(files.iter().map(|a|a.filename.as_str()).collect::<Vec<_>>(), ["texture000.json", "texture001.json"]);
+    let page: serde_json::Value = serde_json::from_slice(&files[0].content).unwrap();
+    let frame = &page["frames"]["hero/walk_02.png"];
+    assert_eq!(frame["frame"], serde_json::json!({ "x": 32, "y": 0, "w": 32, "h": 48 }));
+    assert_eq!(frame["rotated"], true);
+    assert_eq!(frame["sourceSize"], serde_json::json!({ "w": 32, "h": 48 }));
+    assert_eq!(page["meta"]["image"], "texture000.png");
+    assert_eq!(page["meta"]["size"], serde_json::json!({ "w": 128, "h": 128 }));
+    assert_eq!(page["meta"]["related_multi_packs"], serde_json::json!(["texture001.json"]));
+
+    let files = export(DataFormat::TexturePackerArray, &data, "texture-information.json").unwrap();
+    let page: serde_json::Value = serde_json::from_slice(&files[1].content).unwrap();
+    assert_eq!(page["frames"][0]["filename"], "tree.png");
+    assert_eq!(page["frames"].as_array().unwrap().len(), 1);
+
+    // meta.size can not be written without the texture size
+    let mut unknown_size = output_data();
+    unknown_size.texture_size = [0, 0];
+    let error = export(DataFormat::TexturePackerHash, &unknown_size, "texture-information.json").unwrap_err();
+    assert!(error.contains("texture size is unknown"), "{}", error);
+}
+
+#[test]
+fn test_libgdx() {
+    assert_eq!(libgdx::region_name_and_index("hero/walk_01.png"), ("hero/walk", 1));
+    assert_eq!(libgdx::region_name_and_index("walk_12"), ("walk", 12));
+    assert_eq!(libgdx::region_name_and_index("tree.png"), ("tree", -1));
+    assert_eq!(libgdx::region_name_and_index("a.b/walk_x"), ("a.b/walk_x", -1));
+    assert_eq!(libgdx::region_name_and_index("walk_.png"), ("walk_", -1));
+
+    let mut data = output_data();
+    assert!(export(DataFormat::Libgdx, &data, "texture-information.json").is_err());
+    data.image_layouts[1].rotated = false;
+    let files = export(DataFormat::Libgdx, &data, "texture-information.json").unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].filename, "texture-information.atlas");
+    let atlas = String::from_utf8(files[0].content.clone()).unwrap();
+    assert!(atlas.starts_with("\ntexture000.png\nsize: 128,128\nformat: RGBA8888\nfilter: Nearest,Nearest\nrepeat: none\nhero/walk\n  rotate: false\n  xy: 0, 0\n  size: 32, 48\n  orig: 32, 48\n  offset: 0, 0\n  index: 1\n"));
+    assert!(atlas.contains("\ntexture001.png\n"));
+    assert!(atlas.ends_with("tree\n  rotate: false\n  xy: 0, 0\n  size: 64, 64\n  orig: 64, 64\n  offset: 0, 0\n  index: -1\n"));
+
+    // pages can not be written without their size
+    data.texture_size = [0, 128];
+    let error = export(DataFormat::Libgdx, &data, "texture-information.json").unwrap_err();
+    assert!(error.contains("texture size is unknown"), "{}", error);
+
+    // sprites rotated counter clockwise are written with their original size
+    let mut data = output_data();
+    data.rotation = Rotation::CounterClockwise;
+    let files = export(DataFormat::Libgdx, &data, "texture-information.json").unwrap();
+    let atlas = String::from_utf8(files[0].content.clone()).unwrap();
+    assert!(atlas.contains("hero/walk\n  rotate: true\n  xy: 32, 0\n  size: 32, 48\n  orig: 32, 48\n  offset: 0, 0\n  index: 2\n"));
+    assert!(export(DataFormat::TexturePackerHash, &data, "texture-information.json").is_err());
+    assert!(export(DataFormat::Starling, &data, "texture-information.json").is_err());
+    assert!(export(DataFormat::Cocos2dPlist, &data, "texture-information.json").is_err());
+
+    assert_eq!(DataFormat::Libgdx.rotations(), [Rotation::CounterClockwise]);
+    assert!(DataFormat::Css.rotations().is_empty());
+    assert!(DataFormat::Binary.rotations().contains(&Rotation::CounterClockwise));
+}
+
+#[test]
+fn test_godot() {
+    let mut data = output_data();
+    assert!(export(DataFormat::Godot, &data, "texture-information.json").is_err());
+    data.image_layouts[1].rotated = false;
+    let files = export(DataFormat::Godot, &data, "texture-information.json").unwrap();
+    assert_eq!(
+        files.iter().map(|a|a.filename.as_str()).collect::<Vec<_>>(),
+        ["hero/walk_01.tres", "hero/walk_02.tres", "tree.tres", "texture-information.gd"]);
+    assert_eq!(String::from_utf8(files[1].content.clone()).unwrap(), "\
+[gd_resource type=\"AtlasTexture\" load_steps=2 format=3]
+
+[ext_resource type=\"Texture2D\" path=\"../texture000.png\" id=\"1\"]
+
+[resource]
+atlas = ExtResource(\"1\")
+region = Rect2(32, 0, 32, 48)
+margin = Rect2(0, 0, 0, 0)
+");
+    let index = String::from_utf8(files[3].content.clone()).unwrap();
+    assert!(index.contains("\t\"hero/walk_02\": preload(\"hero/walk_02.tres\"),\n"));
+    assert!(index.contains("\t\"tree\": preload(\"tree.tres\"),\n"));
+
+    // control characters in names do not break the string literals
+    data.image_layouts[2].name = String::from("a\"b\n\tc\u{1}.png");
+    let files = export(DataFormat::Godot, &data, "texture-information.json").unwrap();
+    let index = String::from_utf8(files[3].content.clone()).unwrap();
+    assert!(index.contains("\t\"a\\\"b\\n\\tc\\u0001\": preload(\"a\\\"b\\n\\tc\\u0001.tres\"),\n"), "{}", index);
+}
+
+#[test]
+fn test_cocos2d_plist() {
+    let data = output_data();
+    let files = export(DataFormat::Cocos2dPlist, &data, "texture-information.json").unwrap();
+    assert_eq!(files.iter().map(|a|a.filename.as_str()).collect::<Vec<_>>(), ["texture000.plist", "texture001.plist"]);
+    let plist = String::from_utf8(files[0].content.clone()).unwrap();
+    assert!(plist.contains("\
+            <key>hero/walk_02.png</key>
+            <dict>
+                <key>frame</key>
+                <string>{{32,0},{32,48}}</string>
+                <key>offset</key>
+                <string>{0,0}</string>
+                <key>rotated</key>
+                <true/>
+                <key>sourceColorRect</key>
+                <string>{{0,0},{32,48}}</string>
+                <key>sourceSize</key>
+                <string>{32,48}</string>
+            </dict>
+"));
+    assert!(plist.contains("<key>format</key>\n            <integer>2</integer>"));
+    assert!(plist.contains("<key>size</key>\n            <string>{128,128}</string>"));
+    assert!(plist.contains("<key>textureFileName</key>\n            <string>texture000.png</string>"));
+
+    let files = export(DataFormat::Cocos2dPlistV3, &data, "texture-information.json").unwrap();
+    let plist = String::from_utf8(files[1].content.clone()).unwrap();
+    assert!(plist.contains("<key>textureRect</key>\n                <string>{{0,0},{64,64}}</string>"));
+    assert!(plist.contains("<key>textureRotated</key>\n                <false/>"));
+    assert!(plist.contains("<key>format</key>\n            <integer>3</integer>"));
+
+    let mut unknown_size = output_data();
+    unknown_size.texture_size = [0, 0];
+    let error = export(DataFormat::Cocos2dPlist, &unknown_size, "texture-information.json").unwrap_err();
+    assert!(error.contains("texture size is unknown"), "{}", error);
+}
+
+#[test]
+fn test_starling() {
+    let mut data = output_data();
+    data.image_layouts[2].name = String::from("a&b.png");
+    let files = export(DataFormat::Starling, &data, "texture-information.json").unwrap();
+    assert_eq!(files.iter().map(|a|a.filename.as_str()).collect::<Vec<_>>(), ["texture000.xml", "texture001.xml"]);
+    assert_eq!(String::from_utf8(files[0].content.clone()).unwrap(), "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<TextureAtlas imagePath=\"texture000.png\">
+    <SubTexture name=\"hero/walk_01.png\" x=\"0\" y=\"0\" width=\"32\" height=\"48\" frameX=\"0\" frameY=\"0\" frameWidth=\"32\" frameHeight=\"48\" rotated=\"false\"/>
+    <SubTexture name=\"hero/walk_02.png\" x=\"32\" y=\"0\" width=\"48\" height=\"32\" frameX=\"0\" frameY=\"0\" frameWidth=\"32\" frameHeight=\"48\" rotated=\"true\"/>
+</TextureAtlas>
+");
+    assert!(String::from_utf8(files[1].content.clone()).unwrap().contains("name=\"a&amp;b.png\""));
+}
+
+#[test]
+fn test_css() {
+    assert_eq!(css::class_name("icons/home.png"), "icons-home");
+    assert_eq!(css::class_name("1st place.png"), "_1st-place");
+    assert_eq!(css::class_name("-1icon.png"), "_-1icon");
+    assert_eq!(css::class_name("--icon.png"), "_--icon");
+    assert_eq!(css::class_name("-icon.png"), "-icon");
+    assert_eq!(css::class_name("-.png"), "_-");
+
+    let mut data = output_data();
+    data.image_layouts[1].rotated = false;
+    data.image_layouts.push(ImageLayoutInfo { name: String::from("tree@2x.png"), texture: 0, position: [64, 1], size: [128, 128], rotated: false });
+    let files = export(DataFormat::Css, &data, "texture-information.json").unwrap();
+    assert_eq!(files[0].filename, "texture-information.css");
+    let css = String::from_utf8(files[0].content.clone()).unwrap();
+    assert!(css.contains("\
+.hero-walk_02 {
+  background-image: url(\"texture000.png\");
+  background-repeat: no-repeat;
+  background-position: -32px 0;
+  width: 32px;
+  height: 48px;
+}
+"));
+    assert!(!css.contains(".tree-2x"));
+    assert!(css.ends_with("\
+@media (-webkit-min-device-pixel-ratio: 2), (min-resolution: 192dpi) {
+  .tree {
+    background-image: url(\"texture000.png\");
+    background-position: -32px -0.5px;
+    background-size: 64px 64px;
+  }
+}
+"));
+
+    let files = export(DataFormat::Scss, &data, "texture-information.json").unwrap();
+    let scss = String::from_utf8(files[0].content.clone()).unwrap();
+    assert!(scss.contains("$tree: (image: \"texture001.png\", x: 0, y: 0, width: 64px, height: 64px);\n"));
+    assert!(scss.contains("$tree-2x: (image: \"texture000.png\", x: 32px, y: 0.5px, background-width: 64px, background-height: 64px);\n"));
+    assert!(scss.contains("@mixin sprite($sprite) {"));
+
+    // retina backgrounds need the texture size
+    data.texture_size = [0, 0];
+    for format in vec![DataFormat::Css, DataFormat::Scss].into_iter() {
+        let error = export(format, &data, "texture-information.json").unwrap_err();
+        assert!(error.contains("texture size is unknown"), "{}", error);
+    }
+    data.image_layouts.pop();
+    assert!(export(DataFormat::Css, &data, "texture-information.json").is_ok());
+    data.texture_size = [128, 128];
+
+    data.image_layouts.push(ImageLayoutInfo { name: String::from("hero walk_01.png"), texture: 0, position: [0, 0], size: [1, 1], rotated: false });
+    data.image_layouts[0].name = String::from("hero-walk_01.png");
+    assert!(export(DataFormat::Css, &data, "texture-information.json").is_err());
+}
+
+#[test]
+fn test_rust_module() {
+    assert_eq!(rust::const_name("hero/walk_01.png"), "HERO_WALK_01");
+    assert_eq!(rust::const_name("1up.png"), "_1UP");
+    assert_eq!(rust::variant_name("hero/walk_01.png"), "HeroWalk01");
+    assert_eq!(rust::variant_name("1up.png"), "Sprite1up");
+    assert_eq!(rust::variant_name("self.png"), "Self_");
+
+    let data = output_data();
+    let source = rust::module_source(&data).unwrap();
+    assert!(source.contains("#[allow(dead_code)]\npub const TEXTURE_SIZE: [u32; 2] = [128, 128];\n"));
+    assert!(source.contains("#[allow(dead_code, unused_imports)]\npub mod sprites {\n"));
+    assert!(source.contains("pub const TEXTURES: [&str; 2] = [\"texture000.png\", \"texture001.png\"];\n"));
+    assert!(source.contains("    pub const HERO_WALK_02: Sprite = Sprite { texture: 0, x: 32, y: 0, width: 32, height: 48, rotated: true, uv: [0.25, 0.0, 0.625, 0.25] };\n"));
+    assert!(source.contains("    pub const TREE: Sprite = Sprite { texture: 1, x: 0, y: 0, width: 64, height: 64, rotated: false, uv: [0.0, 0.0, 0.5, 0.5] };\n"));
+    assert!(source.contains("            SpriteId::HeroWalk02 => sprites::HERO_WALK_02,\n"));
+    assert!(source.contains("            SpriteId::Tree => \"tree.png\",\n"));
+
+    let mut colliding = output_data();
+    colliding.image_layouts[2].name = String::from("hero-walk_01.png");
+    assert!(rust::module_source(&colliding).is_err());
+
+    // sprites named like the module items do not clash with them
+    let mut reserved = output_data();
+    reserved.image_layouts[0].name = String::from("textures.png");
+    reserved.image_layouts[1].name = String::from("texture_size.png");
+    let reserved_source = rust::module_source(&reserved).unwrap();
+    assert!(reserved_source.contains("    pub const TEXTURES: Sprite = Sprite { texture: 0,"));
+    assert!(reserved_source.contains("    pub const TEXTURE_SIZE: Sprite = Sprite { texture: 0,"));
+    assert!(reserved_source.contains("            SpriteId::TextureSize => sprites::TEXTURE_SIZE,\n"));
+
+    // json written before texture_size was recorded has no uv
+    let mut unknown_size = output_data();
+    unknown_size.texture_size = [0, 0];
+    let error = rust::module_source(&unknown_size).unwrap_err();
+    assert!(error.contains("texture size is unknown"), "{}", error);
+
+    // build.rs usage
+    let dir = temp_dir("rust-module");
+    std::fs::write(dir.join("texture-information.json"), serde_json::to_vec(&data).unwrap()).unwrap();
+    rust::write_module(&dir.join("texture-information.json"), &dir.join("sprites.rs")).unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("sprites.rs")).unwrap(), source);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// the module must build as a 2021 crate without warnings, as a consumer would include! it
+fn compile_rust_module(dir: &std::path::Path, name: &str, data: &OutputData) {
+    let path = dir.join(format!("{}.rs", name));
+    std::fs::write(&path, rust::module_source(data).unwrap()).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_|String::from("rustc"));
+    let output = std::process::Command::new(rustc)
+        .args(["--crate-type", "lib", "--edition", "2021", "-D", "warnings", "--out-dir"])
+        .arg(dir)
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}: {}", name, String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_rust_module_compiles() {
+    let dir = temp_dir("rust-module-compiles");
+    compile_rust_module(&dir, "sprites", &output_data());
+
+    let empty = OutputData { image_layouts: Vec::new(), textures: Vec::new(), texture_size: [128, 128], rotation: Rotation::Clockwise, statistics: None };
+    compile_rust_module(&dir, "empty", &empty);
+
+    // names made into keywords or starting with a digit
+    let mut reserved = output_data();
+    reserved.image_layouts[0].name = String::from("self.png");
+    reserved.image_layouts[1].name = String::from("1up.png");
+    reserved.image_layouts[2].name = String::from("_.png");
+    let source = rust::module_source(&reserved).unwrap();
+    assert!(source.contains("    Self_,\n    Sprite1up,\n    Sprite,\n"), "{}", source);
+    compile_rust_module(&dir, "reserved", &reserved);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_c_header() {
+    assert_eq!(c_header::identifier("hero/walk_01.png"), "SPRITE_ID_HERO_WALK_01");
+    assert_eq!(c_header::identifier("1up.png"), "SPRITE_ID_1UP");
+
+    let data = output_data();
+    let files = export(DataFormat::CHeader, &data, "texture-information.json").unwrap();
+    assert_eq!(files[0].filename, "texture-information.h");
+    let header = String::from_utf8(files[0].content.clone()).unwrap();
+    assert!(header.contains("#ifndef TEXTURE_INFORMATION_H\n#define TEXTURE_INFORMATION_H\n"));
+    assert!(header.contains("#define SPRITE_COUNT 3\n"));
+    assert!(header.contains("\
+typedef struct SpriteRect {
+    int texture;
+    int x;
+    int y;
+    int width;
+    int height;
+    /* rotated 90 degrees clockwise in the texture */
+    int rotated;
+    float u0;
+    float v0;
+    float u1;
+    float v1;
+    const char *name;
+} SpriteRect;
+
+enum SpriteId {
+"), "{}", header);
+    assert!(header.contains("    SPRITE_ID_HERO_WALK_02 = 1,\n"));
+    assert!(header.contains("    { 0, 32, 0, 32, 48, 1, 0.25f, 0.0f, 0.625f, 0.25f, \"hero/walk_02.png\" }, /* SPRITE_ID_HERO_WALK_02 */\n"));
+    assert!(header.ends_with("#endif /* TEXTURE_INFORMATION_H */\n"));
+
+    let mut colliding = output_data();
+    colliding.image_layouts[2].name = String::from("hero-walk_01.png");
+    assert!(export(DataFormat::CHeader, &colliding, "texture-information.json").is_err());
+    let mut unknown_size = output_data();
+    unknown_size.texture_size = [128, 0];
+    let error = c_header::header_source(&unknown_size, "TEXTURE_INFORMATION_H").unwrap_err();
+    assert!(error.contains("texture size is unknown"), "{}", error);
+
+    // sprites named like the header macros and tables do not clash with them
+    let mut reserved = output_data();
+    reserved.image_layouts[0].name = String::from("count.png");
+    reserved.image_layouts[1].name = String::from("textures.png");
+    reserved.image_layouts[2].name = String::from("texture_count.png");
+    let files = export(DataFormat::CHeader, &reserved, "texture-information.json").unwrap();
+    let header = String::from_utf8(files[0].content.clone()).unwrap();
+    assert!(header.contains("    SPRITE_ID_COUNT = 0,\n    SPRITE_ID_TEXTURES = 1,\n    SPRITE_ID_TEXTURE_COUNT = 2,\n"));
+    assert!(header.contains("#define SPRITE_COUNT 3\n"));
+    reserved.image_layouts[0].name = String::from("count_h.png");
+    assert!(export(DataFormat::CHeader, &reserved, "sprite_id_count.json").is_err());
+
+    // no sprites
+    let empty = OutputData { image_layouts: Vec::new(), textures: Vec::new(), texture_size: [128, 128], rotation: Rotation::Clockwise, statistics: None };
+    let header = c_header::header_source(&empty, "EMPTY_H").unwrap();
+    assert!(header.contains("enum SpriteId {\n    SPRITE_ID_NONE_ = 0\n};\n"));
+    assert!(header.contains("#define SPRITE_COUNT 0\n"));
+    assert!(header.contains("static const SpriteRect SPRITES[1] = { { 0 } };\n"));
+
+    // control characters are written as octal escapes which do not take the following digits
+    let mut control = output_data();
+    control.image_layouts[2].name = String::from("a\"b\n\tc\u{1}1\u{85}??=.png");
+    let header = c_header::header_source(&control, "TEXTURE_INFORMATION_H").unwrap();
+    assert!(header.contains(" \"a\\\"b\\n\\tc\\0011\\302\\205\\?\\?=.png\" }, /* SPRITE_ID_A_B__C_1____ */\n"), "{}", header);
+}
+
+// the header must be valid c without warnings
+fn compile_c_header(dir: &std::path::Path, name: &str, data: &OutputData) {
+    let path = dir.join(format!("{}.h", name));
+    std::fs::write(&path, c_header::header_source(data, "TEXTURE_INFORMATION_H").unwrap()).unwrap();
+    let cc = std::env::var("CC").unwrap_or_else(|_|String::from("cc"));
+    let output = std::process::Command::new(cc)
+        .args(["-fsyntax-only", "-Wall", "-Werror"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}: {}", name, String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_c_header_compiles() {
+    let dir = temp_dir("c-header-compiles");
+    compile_c_header(&dir, "sprites", &output_data());
+
+    let empty = OutputData { image_layouts: Vec::new(), textures: Vec::new(), texture_size: [128, 128], rotation: Rotation::Clockwise, statistics: None };
+    compile_c_header(&dir, "empty", &empty);
+
+    // names with escapes, and names like the header macros
+    let mut escaped = output_data();
+    escaped.image_layouts[0].name = String::from("a\"b\\\n\tc\u{1}1\u{85}??=.png");
+    escaped.image_layouts[1].name = String::from("count.png");
+    escaped.image_layouts[2].name = String::from("1up.png");
+    compile_c_header(&dir, "escaped", &escaped);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use image_packer::input::*;
+use regex::Regex;
+use std::path::Path;
+use common::temp_dir;
+
+fn create_files(dir: &Path, names: &[&str]) {
+    for name in names {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"").unwrap();
+    }
+}
+
+#[test]
+fn test_collect_directory() {
+    let dir = temp_dir("collect-directory");
+    create_files(&dir, &["top.png", "ui/ok.png", "hud/ok.png", "hud/sub/x.png", "hud/readme.txt"]);
+    let mut options = InputOptions {
+        recursive: false,
+        filename_pattern: Some(Regex::new(r"\.png$").unwrap()),
+        any_extension: false,
+    };
+
+    let images = collect_directory(&dir, &options).unwrap().images;
+    let names: Vec<&str> = images.iter().map(|a|a.name.as_str()).collect();
+    assert_eq!(vec!["top.png"], names);
+
+    options.recursive = true;
+    let images = collect_directory(&dir, &options).unwrap().images;
+    let names: Vec<&str> = images.iter().map(|a|a.name.as_str()).collect();
+    assert_eq!(vec!["hud/ok.png", "hud/sub/x.png", "top.png", "ui/ok.png"], names);
+    assert!(check_duplicate_names(&images).is_ok());
+
+    let duplicated = vec![images[0].clone(), InputImage { path: dir.join("ui/ok.png"), name: images[0].name.clone() }];
+    assert!(check_duplicate_names(&duplicated).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_collect_inputs() {
+    let dir = temp_dir("collect-inputs");
+    create_files(&dir, &["art/ui/ok.png", "art/hud/ok.png", "art/hud/ok.txt", "extra/a.png", "extra/b.png", "single/c.png"]);
+    std::fs::write(dir.join("list.txt"), "# comment\nextra\n\nsingle/c.png\n").unwrap();
+
+    let sources = vec![
+        format!("{}/art/**/*.png", dir.display()),
+        format!("@{}", dir.join("list.txt").display()),
+        dir.join("extra/a.png").to_str().unwrap().to_string(),
+    ];
+    let inputs = collect_inputs(&sources, &InputOptions::default()).unwrap();
+    let names: Vec<&str> = inputs.images.iter().map(|a|a.name.as_str()).collect();
+    assert_eq!(vec!["hud/ok.png", "ui/ok.png", "a.png", "b.png", "c.png"], names);
+    assert!(check_duplicate_names(&inputs.images).is_ok());
+    assert!(inputs.skipped.is_empty());
+
+    assert!(collect_inputs(&[dir.join("missing").to_str().unwrap().to_string()], &InputOptions::default()).is_err());
+
+    // unknown extensions are skipped in directories unless any_extension is set
+    let art = vec![dir.join("art").to_str().unwrap().to_string()];
+    let options = InputOptions { recursive: true, ..Default::default() };
+    let inputs = collect_inputs(&art, &options).unwrap();
+    assert_eq!(2, inputs.images.len());
+    assert_eq!(vec![dir.join("art/hud/ok.txt")], inputs.skipped);
+    let options = InputOptions { recursive: true, any_extension: true, ..Default::default() };
+    let inputs = collect_inputs(&art, &options).unwrap();
+    assert_eq!(3, inputs.images.len());
+
+    // the pattern filters glob matches as well as directories
+    let glob = vec![format!("{}/art/**/*", dir.display())];
+    let options = InputOptions { filename_pattern: Some(Regex::new(r"\.txt$").unwrap()), any_extension: true, ..Default::default() };
+    let inputs = collect_inputs(&glob, &options).unwrap();
+    let names: Vec<&str> = inputs.images.iter().map(|a|a.name.as_str()).collect();
+    assert_eq!(vec!["hud/ok.txt"], names);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_collect_inputs_glob_characters() {
+    let dir = temp_dir("collect-inputs-glob-characters");
+    create_files(&dir, &["sprites[old]/a.png", "sprites[old]/b.png"]);
+
+    // existing paths are taken literally
+    let inputs = collect_inputs(&[dir.join("sprites[old]").display().to_string()], &InputOptions::default()).unwrap();
+    let names: Vec<&str> = inputs.images.iter().map(|a|a.name.as_str()).collect();
+    assert_eq!(vec!["a.png", "b.png"], names);
+
+    // patterns matching nothing are errors
+    assert!(collect_inputs(&[format!("{}/sprites*/*.pgn", dir.display())], &InputOptions::default()).is_err());
+    let options = InputOptions { filename_pattern: Some(Regex::new(r"^c").unwrap()), ..Default::default() };
+    assert!(collect_inputs(&[format!("{}/sprites*/*.png", dir.display())], &options).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_collect_inputs_same_file() {
+    let dir = temp_dir("collect-inputs-same-file");
+    create_files(&dir, &["art/a.png", "art/sub/b.png"]);
+
+    // the same file through different paths is packed once
+    let sources = vec![
+        dir.join("art/a.png").display().to_string(),
+        dir.join("art/sub/../a.png").display().to_string(),
+    ];
+    let inputs = collect_inputs(&sources, &InputOptions::default()).unwrap();
+    assert_eq!(1, inputs.images.len());
+    assert_eq!("a.png", inputs.images[0].name);
+
+    // the same file with different names is an error
+    let sources = vec![
+        dir.join("art").display().to_string(),
+        dir.join("art/sub/b.png").display().to_string(),
+    ];
+    let options = InputOptions { recursive: true, ..Default::default() };
+    assert!(collect_inputs(&sources, &options).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_collect_directory_symlink_cycle() {
+    let dir = temp_dir("collect-directory-symlink-cycle");
+    create_files(&dir, &["art/a.png", "art/sub/b.png"]);
+    std::os::unix::fs::symlink(dir.join("art"), dir.join("art/sub/parent")).unwrap();
+    std::os::unix::fs::symlink(dir.join("art/a.png"), dir.join("art/sub/link.png")).unwrap();
+
+    // links to directories are not followed, links to files are taken
+    let options = InputOptions { recursive: true, ..Default::default() };
+    let inputs = collect_directory(&dir.join("art"), &options).unwrap();
+    let names: Vec<&str> = inputs.images.iter().map(|a|a.name.as_str()).collect();
+    assert_eq!(vec!["a.png", "sub/b.png", "sub/link.png"], names);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
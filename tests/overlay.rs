@@ -0,0 +1,99 @@
+use image::{Rgba, RgbaImage};
+use image_packer::*;
+use image_packer::overlay::*;
+
+fn packed(layouts: Vec<Layout>, free_spaces: Vec<Rect>) -> PackedTexture {
+    PackedTexture {
+        layouts,
+        statistics: TextureStatistics { sprite_count: 0, used_area: 0, wasted_area: 0, occupancy: 0.0, largest_free_rect: [0, 0] },
+        free_spaces,
+    }
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|a|a.to_string()).collect()
+}
+
+const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+#[test]
+fn test_outline_spacing_and_free_space() {
+    let mut image = RgbaImage::new(32, 32);
+    let packed = packed(
+        vec![Layout { index: 0, position: [0, 0], rotated: false }],
+        vec![Rect { position: [0, 16], size: [32, 16] }]);
+    draw_debug_overlay(&mut image, &packed, &[[16, 12]], &names(&["a.png"]), &[false], 2);
+
+    // outline on the sprite edges in the color of its index
+    let outline = Rgba([255, 64, 64, 255]);
+    for (x, y) in [(0, 0), (15, 0), (0, 11), (15, 11), (15, 5), (7, 11)] {
+        assert_eq!(*image.get_pixel(x, y), outline, "({}, {})", x, y);
+    }
+
+    // spacing gutters at the right and the bottom are shaded
+    let gutter = Rgba([64, 0, 0, 64]);
+    for (x, y) in [(16, 0), (17, 13), (5, 12), (15, 13)] {
+        assert_eq!(*image.get_pixel(x, y), gutter, "({}, {})", x, y);
+    }
+    assert_eq!(*image.get_pixel(18, 5), CLEAR);
+    assert_eq!(*image.get_pixel(5, 14), CLEAR);
+
+    // every sixth diagonal of the free space is hatched
+    let hatch = Rgba([80, 80, 80, 160]);
+    assert_eq!(*image.get_pixel(2, 16), hatch);
+    assert_eq!(*image.get_pixel(8, 16), hatch);
+    assert_eq!(*image.get_pixel(3, 16), CLEAR);
+    assert_eq!(*image.get_pixel(31, 31), CLEAR);
+    assert_eq!(*image.get_pixel(30, 30), hatch);
+}
+
+#[test]
+fn test_label_clipped_to_sprite() {
+    // "#0 abc.png" has room for 2 characters in 14 pixels
+    let draw = |name: &str| {
+        let mut image = RgbaImage::new(32, 32);
+        let packed = packed(vec![Layout { index: 0, position: [4, 4], rotated: false }], Vec::new());
+        draw_debug_overlay(&mut image, &packed, &[[14, 12]], &names(&[name]), &[false], 0);
+        image
+    };
+    let image = draw("abc.png");
+    assert_eq!(image, draw("xyz.png"));
+
+    // label background and text stay inside the outline
+    assert_eq!(*image.get_pixel(5, 5), Rgba([0, 0, 0, 192]));
+    for y in 0..32 {
+        for x in 0..32 {
+            if !(4..18).contains(&x) || !(4..16).contains(&y) {
+                assert_eq!(*image.get_pixel(x, y), CLEAR, "({}, {})", x, y);
+            }
+        }
+    }
+    // the glyph of '#' starts with 0x0A in its first row
+    assert_eq!(*image.get_pixel(6, 6), Rgba([0, 0, 0, 192]));
+    assert_eq!(*image.get_pixel(7, 6), Rgba([255, 255, 255, 255]));
+
+    // no label in a sprite too small for a character
+    let mut image = RgbaImage::new(32, 32);
+    let packed = packed(vec![Layout { index: 0, position: [0, 0], rotated: false }], Vec::new());
+    draw_debug_overlay(&mut image, &packed, &[[6, 6]], &names(&["a.png"]), &[false], 0);
+    assert_eq!(*image.get_pixel(1, 1), CLEAR);
+}
+
+#[test]
+fn test_failed_sprite() {
+    let mut image = RgbaImage::new(32, 32);
+    let packed = packed(vec![
+        Layout { index: 0, position: [0, 0], rotated: false },
+        Layout { index: 1, position: [0, 16], rotated: true },
+    ], Vec::new());
+    draw_debug_overlay(&mut image, &packed, &[[16, 16], [8, 16]], &names(&["a.png", "b.png"]), &[false, true], 0);
+
+    // decoded sprites are outlined in color, failed ones in gray and crossed out
+    assert_eq!(*image.get_pixel(15, 15), Rgba([255, 64, 64, 255]));
+    let failed = Rgba([96, 96, 96, 255]);
+    assert_eq!(*image.get_pixel(15, 23), failed);
+    assert_eq!(*image.get_pixel(0, 16), failed);
+    assert_eq!(*image.get_pixel(6, 19), failed);
+    assert_eq!(*image.get_pixel(9, 19), failed);
+    assert_eq!(*image.get_pixel(8, 21), CLEAR);
+}
@@ -21,7 +21,7 @@ proptest! {
         prop_assert_eq!(sizes.len(), size_sum, "{:?} {:?}", sizes, results);
 
         // assert all indices exist
-        let mut indices = results.iter().map(|a|a.iter().map(|b|b.index).collect::<Vec<usize>>()).flatten().collect::<Vec<usize>>();
+        let mut indices = results.iter().flat_map(|a|a.iter().map(|b|b.index).collect::<Vec<usize>>()).collect::<Vec<usize>>();
         indices.sort();
         for (index, actual) in indices.iter().enumerate() {
             prop_assert_eq!(index, *actual);
@@ -29,10 +29,10 @@ proptest! {
 
         // assert all images are layouted inside of texture
         let texture = Rect { position: [0, 0], size: texture_size };
-        for layout in results.iter().map(|a|a.iter()).flatten().collect::<Vec<&Layout>>() {
+        for layout in results.iter().flat_map(|a|a.iter()).collect::<Vec<&Layout>>() {
             let size = if layout.rotated
-                    { let s = sizes[layout.index]; [s[1], s[0]] } else
-                    { sizes[layout.index] };
+                    { let s = sizes[layout.index]; [s[1], s[0]] }
+                    else { sizes[layout.index] };
             let rect = Rect { position: layout.position, size };
             prop_assert!(texture.include(&rect));
         }
@@ -43,12 +43,12 @@ proptest! {
                 for layout2 in layouts.iter() {
                     if layout1.index < layout2.index {
                         let size1 = if layout1.rotated
-                                { let s = sizes[layout1.index]; [s[1], s[0]] } else
-                                { sizes[layout1.index] };
+                                { let s = sizes[layout1.index]; [s[1], s[0]] }
+                                else { sizes[layout1.index] };
                         let rect1 = Rect { position: layout1.position, size: [size1[0] + spacing, size1[1] + spacing] };
                         let size2 = if layout2.rotated
-                                { let s = sizes[layout2.index]; [s[1], s[0]] } else
-                                { sizes[layout2.index] };
+                                { let s = sizes[layout2.index]; [s[1], s[0]] }
+                                else { sizes[layout2.index] };
                         let rect2 = Rect { position: layout2.position, size: [size2[0] + spacing, size2[1] + spacing] };
                         prop_assert!(!rect1.has_intersection(&rect2), "{:?} {:?} {:?}", rect1, rect2, results);
                     }
@@ -57,3 +57,28 @@ proptest! {
         }
     }
 }
+
+#[test]
+fn test_pack_with_statistics() {
+    let packer = Packer {
+        texture_size: [64, 64],
+        spacing: 0,
+        enable_rotate: false,
+    };
+    let results = packer.pack_with_statistics(&[[64, 32], [32, 32], [64, 64]]).unwrap();
+    assert_eq!(2, results.len());
+
+    let statistics = &results[0].statistics;
+    assert_eq!(1, statistics.sprite_count);
+    assert_eq!(64 * 64, statistics.used_area);
+    assert_eq!(0, statistics.wasted_area);
+    assert_eq!(100.0, statistics.occupancy);
+    assert_eq!([0, 0], statistics.largest_free_rect);
+
+    let statistics = &results[1].statistics;
+    assert_eq!(2, statistics.sprite_count);
+    assert_eq!(64 * 32 + 32 * 32, statistics.used_area);
+    assert_eq!(32 * 32, statistics.wasted_area);
+    assert_eq!(75.0, statistics.occupancy);
+    assert_eq!([32, 32], statistics.largest_free_rect);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use image_packer::*;
+use image_packer::report::*;
+use common::{output_data, temp_dir};
+
+#[test]
+fn test_html_report() {
+    let mut data = output_data();
+    data.image_layouts[0].name = String::from("say \"hi\" & <bye>.png");
+    data.image_layouts[1].name = String::from("it's.png");
+    data.textures[0] = String::from("a&b.png");
+    let html = html_report(&data, "");
+    assert!(html.contains("<h1>2 textures, 3 images</h1>"));
+    assert!(html.contains("<h2>a&amp;b.png</h2>"));
+    assert!(html.contains("<img src=\"a%26b.png\">"));
+    assert!(html.contains("<div class=\"statistics\">sprites 2, occupancy 18.75%, used area 3072, wasted area 13312</div>"));
+
+    // quotes, ampersands and newlines in titles are escaped
+    assert!(html.contains("\
+<div class=\"sprite\" style=\"left:0px;top:0px;width:32px;height:48px\" \
+title=\"say &quot;hi&quot; &amp; &lt;bye&gt;.png&#10;position: 0, 0&#10;size: 32x48&#10;rotated: false\"></div>"));
+
+    // rotated sprites cover the swapped size in the texture, while the title shows the original size
+    assert!(html.contains("\
+<div class=\"sprite rotated\" style=\"left:32px;top:0px;width:48px;height:32px\" \
+title=\"it&apos;s.png&#10;position: 32, 0&#10;size: 32x48&#10;rotated: true\"></div>"));
+}
+
+#[test]
+fn test_html_report_without_texture_size() {
+    // json written before texture_size was recorded
+    let mut data = output_data();
+    data.texture_size = [0, 0];
+    let html = html_report(&data, "");
+    assert!(html.contains("<div class=\"statistics\">sprites 2</div>"));
+}
+
+#[test]
+fn test_html_report_statistics() {
+    let mut data = output_data();
+    data.statistics = Some(vec![TextureStatistics {
+        sprite_count: 2,
+        used_area: 3072,
+        wasted_area: 13312,
+        occupancy: 18.75,
+        largest_free_rect: [64, 128],
+    }]);
+    let html = html_report(&data, "");
+    assert!(html.contains("<div class=\"statistics\">sprites 2, occupancy 18.75%, used area 3072, wasted area 13312, largest free rect 64x128</div>"));
+}
+
+#[test]
+fn test_relative_texture_dir() {
+    let dir = temp_dir("relative-texture-dir");
+    std::fs::create_dir_all(dir.join("out/textures")).unwrap();
+    std::fs::create_dir_all(dir.join("reports")).unwrap();
+    assert_eq!(relative_texture_dir(&dir.join("out/textures"), &dir.join("out/textures")).unwrap(), "");
+    assert_eq!(relative_texture_dir(&dir.join("out"), &dir.join("out/textures")).unwrap(), "textures/");
+    assert_eq!(relative_texture_dir(&dir.join("reports"), &dir.join("out/textures")).unwrap(), "../out/textures/");
+    assert!(relative_texture_dir(&dir.join("missing"), &dir.join("out")).is_err());
+
+    let html = html_report(&output_data(), "../out/textures/");
+    assert!(html.contains("<img src=\"../out/textures/texture000.png\">"));
+
+    // path segments are percent-encoded for the url
+    let mut data = output_data();
+    data.textures[0] = String::from("page #1?.png");
+    let html = html_report(&data, "my textures/100%/");
+    assert!(html.contains("<img src=\"my%20textures/100%25/page%20%231%3F.png\">"), "{}", html);
+    std::fs::remove_dir_all(&dir).unwrap();
+}